@@ -0,0 +1,439 @@
+//! Save screenshots directly into the `save-directory`, without opening a file dialog
+//!
+//! The name of the file is created from the `filename-template`. It supports these placeholders:
+//!
+//! - `{date}`: Current date and time, e.g. `2025-04-16_13-10-52`.
+//!   Pass a custom format with `{date:%Y-%m-%d}`
+//! - `{width}`: Width of the screenshot
+//! - `{height}`: Height of the screenshot
+//! - `{monitor}`: Name of the monitor the screenshot was taken on
+//! - `{n}`: The smallest positive number which does not result in overwriting an existing file
+//!
+//! To use a literal `{` or `}`, write it twice: `{{` or `}}`
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::image_format::{EncodeOptions, ImageFormat, SaveError};
+
+/// Format used for `{date}` if none is specified
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// Could not save the screenshot into the save directory
+#[derive(thiserror::Error, Debug)]
+pub enum AutoSaveError {
+    /// The template contains a placeholder which does not exist
+    #[error("Unknown placeholder `{{{0}}}` in the filename template")]
+    UnknownPlaceholder(String),
+    /// A `{` in the template does not have a matching `}`
+    #[error("Unclosed `{{` in the filename template")]
+    UnclosedPlaceholder,
+    /// A `}` in the template does not have a matching `{`
+    #[error("Unexpected `}}` in the filename template. Use `}}}}` for a literal `}}`")]
+    UnexpectedClosingBrace,
+    /// Date format passed to `{date:...}` is invalid
+    #[error("Invalid date format `{0}` in the filename template")]
+    InvalidDateFormat(String),
+    /// The template produced an empty file name
+    #[error("The filename template produced an empty file name")]
+    EmptyFileName,
+    /// Could not expand `~` to the home directory
+    #[error("Could not determine the home directory: {0}")]
+    HomeDir(#[from] etcetera::HomeDirError),
+    /// Could not create the save directory
+    #[error("Could not create the save directory {}: {err}", path.display())]
+    CreateDir {
+        /// Path to the save directory
+        path: PathBuf,
+        /// Why we could not create it
+        err: std::io::Error,
+    },
+    /// Could not save the image
+    #[error(transparent)]
+    Save(#[from] SaveError),
+}
+
+/// Values available to the placeholders in the filename template
+#[derive(Debug, Clone)]
+pub struct TemplateValues<'a> {
+    /// When the screenshot was taken
+    pub date: chrono::DateTime<chrono::Local>,
+    /// Width of the screenshot
+    pub width: u32,
+    /// Height of the screenshot
+    pub height: u32,
+    /// Name of the monitor the screenshot was taken on
+    pub monitor: &'a str,
+}
+
+/// Render the filename template, with `n` as the value of `{n}`
+///
+/// # Returns
+///
+/// The rendered file name, and whether the template contains `{n}`
+pub fn render_template(
+    template: &str,
+    values: &TemplateValues,
+    n: u32,
+) -> Result<(String, bool), AutoSaveError> {
+    let mut output = String::new();
+    let mut uses_n = false;
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err(AutoSaveError::UnexpectedClosingBrace),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => placeholder.push(ch),
+                        None => return Err(AutoSaveError::UnclosedPlaceholder),
+                    }
+                }
+
+                let (name, argument) = placeholder
+                    .split_once(':')
+                    .map_or((placeholder.as_str(), None), |(name, arg)| {
+                        (name, Some(arg))
+                    });
+
+                match (name, argument) {
+                    ("date", format) => {
+                        let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+                        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                            return Err(AutoSaveError::InvalidDateFormat(format.to_string()));
+                        }
+                        output.push_str(&values.date.format(format).to_string());
+                    }
+                    ("width", None) => output.push_str(&values.width.to_string()),
+                    ("height", None) => output.push_str(&values.height.to_string()),
+                    ("monitor", None) => output.push_str(values.monitor),
+                    ("n", None) => {
+                        uses_n = true;
+                        output.push_str(&n.to_string());
+                    }
+                    _ => return Err(AutoSaveError::UnknownPlaceholder(placeholder)),
+                }
+            }
+            ch => output.push(ch),
+        }
+    }
+
+    // values such as the monitor name could contain path separators
+    let output = output.replace(['/', '\\'], "-");
+
+    if output.is_empty() {
+        return Err(AutoSaveError::EmptyFileName);
+    }
+
+    Ok((output, uses_n))
+}
+
+/// Create a file inside of `directory`, with the file name generated by the `template`,
+/// without overwriting an existing file.
+///
+/// `try_create` creates the file at the given path and returns `true`, or returns `false`
+/// if there is a file at the path already.
+///
+/// Collisions are resolved deterministically:
+///
+/// - If the template contains `{n}`, then the smallest `n` starting from 1 for which
+///   the file does not exist is used.
+/// - Otherwise, `-1`, `-2`, ... is appended to the file name (before the extension)
+///   until the file does not exist.
+///
+/// # Returns
+///
+/// Path to the created file
+pub fn create_unique<E: From<AutoSaveError>>(
+    directory: &Path,
+    template: &str,
+    values: &TemplateValues,
+    mut try_create: impl FnMut(&Path) -> Result<bool, E>,
+) -> Result<PathBuf, E> {
+    let (file_name, uses_n) = render_template(template, values, 1)?;
+    let path = directory.join(&file_name);
+
+    if try_create(&path)? {
+        return Ok(path);
+    }
+
+    for n in 2.. {
+        let path = if uses_n {
+            directory.join(render_template(template, values, n)?.0)
+        } else {
+            let file_name = Path::new(&file_name);
+            let stem = file_name
+                .file_stem()
+                .map(|stem| stem.to_string_lossy())
+                .unwrap_or_default();
+            let suffixed = file_name.extension().map_or_else(
+                || format!("{stem}-{}", n - 1),
+                |ext| format!("{stem}-{}.{}", n - 1, ext.to_string_lossy()),
+            );
+            directory.join(suffixed)
+        };
+
+        if try_create(&path)? {
+            return Ok(path);
+        }
+    }
+
+    unreachable!("there are only so many files in a directory")
+}
+
+/// The format of the file name generated by the `template`, and the template with the
+/// extension of the format. If the file name has no extension, or one which is not an
+/// image format like `12.30` from `{date:%H.%M}`, the extension of `fallback` is appended
+fn with_format(
+    template: &str,
+    values: &TemplateValues,
+    fallback: ImageFormat,
+) -> Result<(ImageFormat, String), AutoSaveError> {
+    let (file_name, _) = render_template(template, values, 1)?;
+
+    Ok(
+        match Path::new(&file_name)
+            .extension()
+            .and_then(|ext| ImageFormat::from_extension(&ext.to_string_lossy()).ok())
+        {
+            Some(format) => (format, template.to_string()),
+            None => (fallback, format!("{template}.{}", fallback.extension())),
+        },
+    )
+}
+
+/// Save the image into the `directory`, using a file name generated from the `template`
+///
+/// If the file name does not have the extension of an image format, the extension
+/// of `fallback` is appended.
+///
+/// # Returns
+///
+/// Path to the saved image
+pub fn save_to_directory(
+    image: &image::DynamicImage,
+    directory: &str,
+    template: &str,
+    monitor: &str,
+    fallback: ImageFormat,
+    options: &EncodeOptions,
+) -> Result<PathBuf, AutoSaveError> {
+    let directory = crate::util::expand_home(directory)?;
+    std::fs::create_dir_all(&directory).map_err(|err| AutoSaveError::CreateDir {
+        path: directory.clone(),
+        err,
+    })?;
+
+    let values = TemplateValues {
+        date: chrono::Local::now(),
+        width: image.width(),
+        height: image.height(),
+        monitor,
+    };
+
+    let (format, template) = with_format(template, &values, fallback)?;
+    let encoded = format.encode(image, options)?;
+
+    // the file is only created if it does not exist, so that a file created by someone
+    // else in the meantime is never overwritten
+    create_unique(&directory, &template, &values, |path| {
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+            Err(err) => return Err(AutoSaveError::Save(err.into())),
+        };
+
+        if let Err(err) = file.write_all(&encoded) {
+            // don't leave a broken image behind
+            let _ = std::fs::remove_file(path);
+            return Err(AutoSaveError::Save(err.into()));
+        }
+
+        Ok(true)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    /// Values used to fill out the template
+    fn values() -> TemplateValues<'static> {
+        TemplateValues {
+            date: chrono::Local
+                .with_ymd_and_hms(2025, 4, 16, 13, 10, 52)
+                .single()
+                .expect("valid date"),
+            width: 1920,
+            height: 1080,
+            monitor: "HDMI-1",
+        }
+    }
+
+    #[test]
+    fn render() {
+        assert_eq!(
+            render_template("shot_{date}.png", &values(), 1).ok(),
+            Some((String::from("shot_2025-04-16_13-10-52.png"), false)),
+            "default date format"
+        );
+        assert_eq!(
+            render_template("{date:%Y}_{width}x{height}_{monitor}_{n}.png", &values(), 7).ok(),
+            Some((String::from("2025_1920x1080_HDMI-1_7.png"), true)),
+            "all placeholders"
+        );
+        assert_eq!(
+            render_template("{{literal}}", &values(), 1).ok(),
+            Some((String::from("{literal}"), false)),
+            "escaped braces"
+        );
+        assert!(
+            matches!(
+                render_template("{foo}", &values(), 1),
+                Err(AutoSaveError::UnknownPlaceholder(placeholder)) if placeholder == "foo"
+            ),
+            "unknown placeholder"
+        );
+        assert!(
+            matches!(
+                render_template("{width", &values(), 1),
+                Err(AutoSaveError::UnclosedPlaceholder)
+            ),
+            "unclosed placeholder"
+        );
+        assert!(
+            matches!(
+                render_template("{date:%Q}", &values(), 1),
+                Err(AutoSaveError::InvalidDateFormat(_))
+            ),
+            "invalid date format"
+        );
+    }
+
+    #[test]
+    fn resolve_collisions() {
+        let dir = Path::new("dir");
+        let taken = [
+            dir.join("shot.png"),
+            dir.join("shot-1.png"),
+            dir.join("1.png"),
+            dir.join("2.png"),
+        ];
+        let create = |template: &str| {
+            create_unique(dir, template, &values(), |path| {
+                Ok::<_, AutoSaveError>(!taken.iter().any(|taken| taken == path))
+            })
+            .ok()
+        };
+
+        assert_eq!(
+            create("free.png"),
+            Some(dir.join("free.png")),
+            "no collision"
+        );
+        assert_eq!(
+            create("shot.png"),
+            Some(dir.join("shot-2.png")),
+            "append a number before the extension"
+        );
+        assert_eq!(
+            create("{n}.png"),
+            Some(dir.join("3.png")),
+            "increment {{n}}"
+        );
+    }
+
+    #[test]
+    fn format_of_file_name() {
+        let format = |template: &str| with_format(template, &values(), ImageFormat::Png).ok();
+
+        assert_eq!(
+            format("shot.jpeg"),
+            Some((ImageFormat::Jpeg, String::from("shot.jpeg"))),
+            "extension of an image format"
+        );
+        assert_eq!(
+            format("shot"),
+            Some((ImageFormat::Png, String::from("shot.png"))),
+            "no extension"
+        );
+        assert_eq!(
+            format("{date:%H.%M}"),
+            Some((ImageFormat::Png, String::from("{date:%H.%M}.png"))),
+            "a dot in the date is not an extension"
+        );
+        assert_eq!(
+            with_format(
+                "{monitor}",
+                &TemplateValues {
+                    monitor: r"\\.\DISPLAY1",
+                    ..values()
+                },
+                ImageFormat::Png
+            )
+            .ok(),
+            Some((ImageFormat::Png, String::from("{monitor}.png"))),
+            "a dot in the monitor name is not an extension"
+        );
+    }
+
+    #[test]
+    fn never_overwrite() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let directory = dir.path().to_string_lossy();
+        let image = image::DynamicImage::new_rgba8(4, 4);
+        let options = EncodeOptions {
+            jpeg_quality: 90,
+            png_compression: crate::image_format::PngCompression::Default,
+            webp_lossless: true,
+        };
+        let save = || {
+            save_to_directory(
+                &image,
+                &directory,
+                "shot",
+                "HDMI-1",
+                ImageFormat::Png,
+                &options,
+            )
+            .expect("save the screenshot")
+        };
+
+        let first = save();
+        std::fs::write(dir.path().join("shot-1.png"), b"someone else's file")
+            .expect("create a file");
+        let second = save();
+
+        assert_eq!(
+            (first, second),
+            (dir.path().join("shot.png"), dir.path().join("shot-2.png")),
+            "existing files are skipped"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("shot-1.png")).ok().as_deref(),
+            Some(b"someone else's file".as_slice()),
+            "the other file is left alone"
+        );
+    }
+}
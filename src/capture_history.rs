@@ -0,0 +1,421 @@
+//! History of recent captures, so that a screenshot is not lost once something else is copied
+//!
+//! Each capture is stored in the `captures` directory inside of the data directory as
+//! `<timestamp>.png`, next to a smaller `<timestamp>.thumb.png` which is shown in the
+//! history browser. The oldest captures are removed once there are more than `history-size`
+//! of them, or once they take up more than `history-max-disk-usage`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    thread::JoinHandle,
+};
+
+use etcetera::BaseStrategy as _;
+use iced::futures::channel::oneshot;
+
+use crate::CONFIG;
+
+/// Width and height which thumbnails are scaled down to fit in
+pub const THUMBNAIL_SIZE: u32 = 256;
+/// Extension of the thumbnails, including the extension of the image
+const THUMBNAIL_EXTENSION: &str = "thumb.png";
+/// Format of the timestamp at the start of the name of each capture.
+/// Sorting the names sorts the captures from oldest to newest
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S%.3f";
+
+/// Captures which are being added to the history in the background
+static RECORDING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Could not read or write the capture history
+#[derive(thiserror::Error, Debug)]
+pub enum CaptureHistoryError {
+    /// Could not determine the data directory
+    #[error("Could not determine the data directory: {0}")]
+    HomeDir(#[from] etcetera::HomeDirError),
+    /// Could not read or write the history directory
+    #[error("Could not access the capture history: {0}")]
+    Io(#[from] std::io::Error),
+    /// Could not encode or decode a capture
+    #[error("Could not store the capture: {0}")]
+    Image(#[from] image::ImageError),
+    /// The capture alone takes up more than `history-max-disk-usage`
+    #[error(
+        "The capture takes up {size} bytes, more than the {max_bytes} bytes the history may use"
+    )]
+    TooLarge {
+        /// Size of the capture and its thumbnail
+        size: u64,
+        /// Most bytes the history may take up
+        max_bytes: u64,
+    },
+}
+
+/// How much of the history to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Keep at most this many captures
+    pub max_count: usize,
+    /// Keep at most this many bytes of captures, including their thumbnails
+    pub max_bytes: u64,
+}
+
+impl Limits {
+    /// The limits as configured by the user
+    pub fn from_config(config: &crate::Config) -> Self {
+        Self {
+            max_count: config.history_size as usize,
+            max_bytes: u64::from(config.history_max_disk_usage) * 1024 * 1024,
+        }
+    }
+}
+
+/// A capture in the history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    /// Name of the capture, which starts with the time it was taken
+    pub id: String,
+    /// The full image
+    pub image: PathBuf,
+    /// A smaller version of the image
+    pub thumbnail: PathBuf,
+}
+
+impl Capture {
+    /// The capture with this name in the directory
+    fn new(dir: &Path, id: String) -> Self {
+        Self {
+            image: dir.join(format!("{id}.png")),
+            thumbnail: dir.join(format!("{id}.{THUMBNAIL_EXTENSION}")),
+            id,
+        }
+    }
+
+    /// When the capture was taken, if its name was not changed
+    pub fn taken_at(&self) -> Option<chrono::NaiveDateTime> {
+        // the name may have a `-<n>` suffix if several captures were taken at once
+        let timestamp = self.id.split('-').take(5).collect::<Vec<_>>().join("-");
+        chrono::NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FORMAT).ok()
+    }
+
+    /// Size of the image and the thumbnail on the disk
+    fn disk_usage(&self) -> u64 {
+        [&self.image, &self.thumbnail]
+            .into_iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// Load the full image
+    pub fn load(&self) -> Result<image::DynamicImage, CaptureHistoryError> {
+        Ok(image::open(&self.image)?)
+    }
+
+    /// Remove the capture from the history
+    pub fn delete(&self) -> Result<(), CaptureHistoryError> {
+        std::fs::remove_file(&self.image)?;
+        // a missing thumbnail is only shown as an empty space, so don't fail because of it
+        if let Err(err) = std::fs::remove_file(&self.thumbnail) {
+            log::warn!("Could not remove {}: {err}", self.thumbnail.display());
+        }
+        Ok(())
+    }
+}
+
+/// Location of the capture history
+pub fn history_dir() -> Result<PathBuf, CaptureHistoryError> {
+    Ok(etcetera::choose_base_strategy()?
+        .data_dir()
+        .join("ferrishot")
+        .join("captures"))
+}
+
+/// Every capture in the history, newest first. If there is no history yet, there are no captures
+pub fn list(dir: &Path) -> Result<Vec<Capture>, CaptureHistoryError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut ids = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if name.ends_with(THUMBNAIL_EXTENSION) {
+            continue;
+        }
+        if let Some(id) = name.strip_suffix(".png") {
+            ids.push(id.to_string());
+        }
+    }
+
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+
+    Ok(ids.into_iter().map(|id| Capture::new(dir, id)).collect())
+}
+
+/// Add the image to the history, then remove the oldest captures which are over the `limits`.
+/// Fails if the image does not fit in the history on its own
+pub fn add(
+    dir: &Path,
+    image: &image::DynamicImage,
+    limits: Limits,
+) -> Result<Capture, CaptureHistoryError> {
+    std::fs::create_dir_all(dir)?;
+
+    let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let capture = std::iter::once(timestamp.clone())
+        .chain((1..).map(|n| format!("{timestamp}-{n}")))
+        .map(|id| Capture::new(dir, id))
+        .find(|capture| !capture.image.exists())
+        .expect("there is always a free name");
+
+    image.save_with_format(&capture.image, image::ImageFormat::Png)?;
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .save_with_format(&capture.thumbnail, image::ImageFormat::Png)?;
+
+    let size = capture.disk_usage();
+    if size > limits.max_bytes {
+        capture.delete()?;
+        return Err(CaptureHistoryError::TooLarge {
+            size,
+            max_bytes: limits.max_bytes,
+        });
+    }
+
+    prune(dir, limits)?;
+
+    Ok(capture)
+}
+
+/// Remove the oldest captures until the history is within the `limits`
+pub fn prune(dir: &Path, limits: Limits) -> Result<(), CaptureHistoryError> {
+    let mut count = 0;
+    let mut bytes = 0;
+    let mut is_full = false;
+
+    // once a capture does not fit, it and every capture older than it are removed
+    for capture in list(dir)? {
+        let size = capture.disk_usage();
+        is_full = is_full || count >= limits.max_count || bytes + size > limits.max_bytes;
+
+        if is_full {
+            capture.delete()?;
+        } else {
+            count += 1;
+            bytes += size;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the image to the history, unless the history is disabled. Failing to do so
+/// is not worth bothering the user over, so it is only logged
+///
+/// Encoding the image takes a while, so it happens in the background, which lets the app
+/// close right away. [`finish_recording`] must be called before exiting.
+pub fn record(image: image::DynamicImage) {
+    // nobody is interested in where it is stored
+    let _ = record_with_path(image);
+}
+
+/// Like [`record`], but also tells where the capture is stored
+///
+/// # Returns
+///
+/// Receives the path of the stored capture. It is canceled if the capture is not stored
+pub fn record_with_path(image: image::DynamicImage) -> oneshot::Receiver<PathBuf> {
+    let (sender, receiver) = oneshot::channel();
+
+    let limits = Limits::from_config(&CONFIG);
+    if limits.max_count == 0 {
+        return receiver;
+    }
+
+    let handle = std::thread::spawn(move || {
+        match history_dir().and_then(|dir| add(&dir, &image, limits)) {
+            Ok(capture) => {
+                // nobody may be waiting for the path
+                let _ = sender.send(capture.image);
+            }
+            Err(err) => log::error!("Could not add the capture to the history: {err}"),
+        }
+    });
+
+    RECORDING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(handle);
+
+    receiver
+}
+
+/// Wait for the captures which are being added to the history
+pub fn finish_recording() {
+    let handles = std::mem::take(&mut *RECORDING.lock().unwrap_or_else(PoisonError::into_inner));
+
+    for handle in handles {
+        if handle.join().is_err() {
+            log::error!("Could not add the capture to the history");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Limits which are never reached in the tests
+    const UNLIMITED: Limits = Limits {
+        max_count: 100,
+        max_bytes: u64::MAX,
+    };
+
+    #[test]
+    fn add_and_list() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let dir = dir.path().join("captures");
+
+        assert_eq!(
+            list(&dir).expect("missing history is fine"),
+            [],
+            "no history yet"
+        );
+
+        let first =
+            add(&dir, &image::DynamicImage::new_rgba8(600, 300), UNLIMITED).expect("add a capture");
+        let second =
+            add(&dir, &image::DynamicImage::new_rgba8(10, 10), UNLIMITED).expect("add a capture");
+
+        assert_eq!(
+            list(&dir).expect("list the history"),
+            [second, first.clone()],
+            "newest first"
+        );
+        assert!(first.taken_at().is_some(), "the name contains the time");
+        assert_eq!(
+            image::image_dimensions(&first.thumbnail).ok(),
+            Some((THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2)),
+            "the thumbnail keeps the aspect ratio"
+        );
+        assert_eq!(
+            first.load().map(|image| image.width()).ok(),
+            Some(600),
+            "the full image is kept"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let image = image::DynamicImage::new_rgba8(50, 50);
+
+        for _ in 0..4 {
+            add(dir.path(), &image, UNLIMITED).expect("add a capture");
+        }
+        let captures = list(dir.path()).expect("list the history");
+        let size = captures[0].disk_usage();
+
+        prune(
+            dir.path(),
+            Limits {
+                max_count: 3,
+                ..UNLIMITED
+            },
+        )
+        .expect("prune the history");
+        assert_eq!(
+            list(dir.path()).expect("list the history"),
+            captures[..3],
+            "the oldest capture is removed"
+        );
+
+        prune(
+            dir.path(),
+            Limits {
+                max_bytes: size * 2,
+                ..UNLIMITED
+            },
+        )
+        .expect("prune the history");
+        assert_eq!(
+            list(dir.path()).expect("list the history"),
+            captures[..2],
+            "only the newest captures which fit are kept"
+        );
+
+        captures[0].delete().expect("delete a capture");
+        assert_eq!(
+            list(dir.path()).expect("list the history"),
+            captures[1..2],
+            "the capture is deleted"
+        );
+    }
+
+    #[test]
+    fn older_captures_are_removed_first() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let small = image::DynamicImage::new_rgba8(50, 50);
+        let large = image::DynamicImage::new_rgba8(2000, 2000);
+
+        let oldest = add(dir.path(), &small, UNLIMITED).expect("add a capture");
+        let middle = add(dir.path(), &large, UNLIMITED).expect("add a capture");
+        let newest = add(dir.path(), &small, UNLIMITED).expect("add a capture");
+        assert!(
+            middle.disk_usage() > oldest.disk_usage(),
+            "the large capture takes up more space"
+        );
+
+        prune(
+            dir.path(),
+            Limits {
+                max_bytes: newest.disk_usage() + oldest.disk_usage(),
+                ..UNLIMITED
+            },
+        )
+        .expect("prune the history");
+        assert_eq!(
+            list(dir.path()).expect("list the history"),
+            [newest],
+            "a smaller, older capture is not kept over a larger, newer one"
+        );
+    }
+
+    #[test]
+    fn capture_too_large() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let kept = add(
+            dir.path(),
+            &image::DynamicImage::new_rgba8(10, 10),
+            UNLIMITED,
+        )
+        .expect("add a capture");
+
+        let added = add(
+            dir.path(),
+            &image::DynamicImage::new_rgba8(50, 50),
+            Limits {
+                max_bytes: 1,
+                ..UNLIMITED
+            },
+        );
+        assert!(
+            matches!(
+                added,
+                Err(CaptureHistoryError::TooLarge { max_bytes: 1, .. })
+            ),
+            "the capture does not fit on its own"
+        );
+        assert_eq!(
+            list(dir.path()).expect("list the history"),
+            [kept],
+            "the history is left as it was"
+        );
+    }
+}
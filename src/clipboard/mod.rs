@@ -0,0 +1,219 @@
+//! Provides methods to set the clipboard with images, links and text
+
+#[cfg(target_os = "linux")]
+mod protocol;
+pub mod snippet;
+#[cfg(target_os = "linux")]
+mod wayland;
+#[cfg(target_os = "linux")]
+mod x11;
+
+/// An argument that can be passed into the program to signal that it should daemonize itself. This
+/// can be anything as long as it is unlikely to be passed in by the user by mistake.
+#[cfg(target_os = "linux")]
+pub const CLIPBOARD_DAEMON_ID: &str = "__ferrishot_clipboard_daemon";
+
+/// MIME type of encoded PNG images
+#[cfg(target_os = "linux")]
+pub const PNG_MIME: &str = "image/png";
+/// MIME type of a list of links, one per line. Used by file managers to paste files
+#[cfg(target_os = "linux")]
+pub const URI_LIST_MIME: &str = "text/uri-list";
+/// MIME type of text
+#[cfg(target_os = "linux")]
+pub const TEXT_MIME: &str = "text/plain;charset=utf-8";
+
+/// What to put into the clipboard. Every part is offered at the same time, and the application
+/// that pastes picks the one it understands best: e.g. a chat app pastes the image, a file manager
+/// the file and a terminal the text.
+///
+/// Only Linux offers several parts at once. Elsewhere, only one part is copied: the `text`,
+/// else the `uri`, else the `image`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Content<'a> {
+    /// Offered as `image/png`
+    pub image: Option<&'a image::DynamicImage>,
+    /// Offered as `text/uri-list`
+    pub uri: Option<&'a str>,
+    /// Offered as `text/plain`
+    pub text: Option<&'a str>,
+}
+
+/// Set the content of the clipboard
+///
+/// On Linux, the content is sent to the clipboard daemon through its standard input,
+/// so it is never written to the disk, nor visible in the list of processes
+pub fn set(content: &Content) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut representations = Vec::new();
+        if let Some(image) = content.image {
+            representations.push(protocol::Representation::new(
+                PNG_MIME,
+                crate::image_upload::EncodedImage::png(image)?.bytes,
+            ));
+        }
+        if let Some(uri) = content.uri {
+            // lines of a `text/uri-list` end with CRLF
+            representations.push(protocol::Representation::new(
+                URI_LIST_MIME,
+                format!("{uri}\r\n"),
+            ));
+        }
+        if let Some(text) = content.text {
+            representations.push(protocol::Representation::new(TEXT_MIME, text));
+        }
+
+        spawn_daemon(&representations)?;
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        // the image is only copied along with text where that is possible, so
+        // copying a link must not replace it with the image
+        let mut clipboard = arboard::Clipboard::new()?;
+        if let Some(text) = content.text.or(content.uri) {
+            clipboard.set_text(text)?;
+        } else if let Some(image) = content.image {
+            clipboard.set_image(arboard::ImageData {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                bytes: image.to_rgba8().into_raw().into(),
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the text content of the clipboard
+pub fn set_text(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    set(&Content {
+        text: Some(text),
+        ..Content::default()
+    })
+}
+
+/// `file://` link to the file, which must be absolute
+pub fn file_uri(path: &std::path::Path) -> String {
+    use std::fmt::Write as _;
+
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            let _ = write!(uri, "%{byte:02X}");
+        }
+    }
+    uri
+}
+
+/// Other MIME types under which applications ask for the same content
+#[cfg(target_os = "linux")]
+fn mime_aliases(mime: &str) -> impl Iterator<Item = &str> {
+    let aliases: &[&str] = if mime == TEXT_MIME {
+        // `UTF8_STRING` is used by X11 applications, and by some Wayland ones too
+        &["text/plain", "UTF8_STRING"]
+    } else {
+        &[]
+    };
+
+    std::iter::once(mime).chain(aliases.iter().copied())
+}
+
+/// Spawn the clipboard daemon, and send it the representations to put into the clipboard
+#[cfg(target_os = "linux")]
+fn spawn_daemon(
+    representations: &[protocol::Representation],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::process;
+
+    let mut daemon = process::Command::new(std::env::current_exe()?)
+        .arg(CLIPBOARD_DAEMON_ID)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::inherit())
+        .current_dir("/")
+        .spawn()?;
+
+    // dropping the handle closes the pipe, which tells the daemon that it has the whole frame
+    protocol::encode(
+        representations,
+        &mut daemon
+            .stdin
+            .take()
+            .ok_or("the standard input of the clipboard daemon is not piped")?,
+    )?;
+
+    Ok(())
+}
+
+/// The clipboard daemon could not provide the clipboard
+#[cfg(target_os = "linux")]
+#[derive(thiserror::Error, Debug)]
+pub enum DaemonError {
+    /// Could not read what to put into the clipboard
+    #[error("Invalid message from ferrishot: {0}")]
+    Protocol(#[from] protocol::ProtocolError),
+    /// Could not serve the clipboard on Wayland, nor on X11
+    #[error("Could not set the clipboard: {wayland}, and on X11: {x11}")]
+    Wayland {
+        /// Why it failed on Wayland
+        wayland: wl_clipboard_rs::copy::Error,
+        /// Why it failed on X11 too
+        x11: x11::X11Error,
+    },
+    /// Could not serve the clipboard on X11
+    #[error("Could not set the clipboard: {0}")]
+    X11(#[from] x11::X11Error),
+}
+
+/// Runs a process in the background that provides clipboard access,
+/// until the user copies something else into their clipboard.
+///
+/// The daemon is spawned with `CLIPBOARD_DAEMON_ID` as its only argument, and reads
+/// a single frame from its standard input. See `protocol.rs` for the format of the frame.
+///
+/// # Errors
+///
+/// - The frame is invalid
+/// - Could not connect to the display server
+/// - Could not take ownership of the clipboard
+#[cfg(target_os = "linux")]
+pub fn run_clipboard_daemon() -> Result<(), DaemonError> {
+    log::info!("Spawned clipboard daemon");
+
+    let representations = protocol::decode(&mut std::io::stdin().lock())?;
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Ok(x11::serve(&representations)?);
+    }
+
+    // not every compositor supports the data control protocol, but Xwayland
+    // can still give us the clipboard
+    wayland::serve(&representations).or_else(|wayland| {
+        log::warn!("Could not set the clipboard on Wayland, trying X11: {wayland}");
+        x11::serve(&representations).map_err(|x11| DaemonError::Wayland { wayland, x11 })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn file_uri_escapes() {
+        assert_eq!(
+            file_uri(std::path::Path::new(
+                "/home/me/Pictures/2025-04-16_13-10.png"
+            )),
+            "file:///home/me/Pictures/2025-04-16_13-10.png",
+            "nothing to escape"
+        );
+        assert_eq!(
+            file_uri(std::path::Path::new("/tmp/my screenshot #1 (é).png")),
+            "file:///tmp/my%20screenshot%20%231%20%28%C3%A9%29.png",
+            "spaces, reserved characters and UTF-8 are escaped"
+        );
+    }
+}
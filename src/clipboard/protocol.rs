@@ -0,0 +1,280 @@
+//! Messages sent to the clipboard daemon through its standard input
+//!
+//! Each message is a single frame, with every number in little endian:
+//!
+//! ```text
+//! magic        4 bytes   `FSCB`
+//! version      1 byte    currently 2
+//! count        2 bytes   number of representations, at least 1
+//!
+//! then, for each representation:
+//!
+//! mime length  2 bytes
+//! mime         UTF-8
+//! length       8 bytes   length of the payload
+//! payload      the content, in the format given by the MIME type
+//! ```
+//!
+//! Nothing may follow the last payload.
+
+use std::io::{self, Read, Write};
+
+/// Marks the start of a frame, so that garbage is rejected early
+const MAGIC: &[u8; 4] = b"FSCB";
+/// Version of the protocol. Bump when the format of the frame changes
+const VERSION: u8 = 2;
+/// The largest amount of payload accepted, to avoid allocating huge amounts of memory for a
+/// corrupted length. An 8K screen is only about 130 MB of RGBA pixels, and less once encoded
+const MAX_PAYLOAD_LEN: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Could not decode the message
+#[derive(thiserror::Error, Debug)]
+pub enum ProtocolError {
+    /// Could not read the message
+    #[error("Could not read the message: {0}")]
+    Io(#[from] io::Error),
+    /// The message does not start with the magic bytes
+    #[error("The message is not a clipboard daemon frame")]
+    BadMagic,
+    /// The message was created by an incompatible version of ferrishot
+    #[error("Unsupported protocol version {0}, expected {VERSION}")]
+    UnsupportedVersion(u8),
+    /// There is nothing to put into the clipboard
+    #[error("The message contains no representations")]
+    Empty,
+    /// A MIME type is not UTF-8
+    #[error("Invalid UTF-8 in a MIME type: {0}")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+    /// A MIME type is empty
+    #[error("Empty MIME type")]
+    EmptyMime,
+    /// The payloads are larger than `MAX_PAYLOAD_LEN`
+    #[error("The payload is too large: {0} bytes")]
+    TooLarge(u64),
+    /// There is more data after the last payload
+    #[error("Unexpected data after the payload")]
+    TrailingData,
+}
+
+/// One of the formats in which the content of the clipboard is offered.
+/// The application that pastes picks the one it understands best
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Representation {
+    /// MIME type of the `data`, e.g. `image/png`
+    pub mime: String,
+    /// The content in this format
+    pub data: Vec<u8>,
+}
+
+impl Representation {
+    /// A representation with this MIME type
+    pub fn new(mime: &str, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            mime: mime.to_string(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Write the representations as a frame
+pub fn encode(representations: &[Representation], writer: &mut impl Write) -> io::Result<()> {
+    let too_long =
+        |what: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("{what} is too long"));
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(
+        &u16::try_from(representations.len())
+            .map_err(|_| too_long("the list of representations"))?
+            .to_le_bytes(),
+    )?;
+
+    for Representation { mime, data } in representations {
+        writer.write_all(
+            &u16::try_from(mime.len())
+                .map_err(|_| too_long("the MIME type"))?
+                .to_le_bytes(),
+        )?;
+        writer.write_all(mime.as_bytes())?;
+        writer.write_all(&(data.len() as u64).to_le_bytes())?;
+        writer.write_all(data)?;
+    }
+
+    writer.flush()
+}
+
+/// Read a frame, which must be the only thing in the `reader`
+pub fn decode(reader: &mut impl Read) -> Result<Vec<Representation>, ProtocolError> {
+    let magic = read_array::<4>(reader)?;
+    if &magic != MAGIC {
+        return Err(ProtocolError::BadMagic);
+    }
+
+    let [version] = read_array(reader)?;
+    if version != VERSION {
+        return Err(ProtocolError::UnsupportedVersion(version));
+    }
+
+    let count = u16::from_le_bytes(read_array(reader)?);
+    if count == 0 {
+        return Err(ProtocolError::Empty);
+    }
+
+    let mut total_len = 0_u64;
+    let mut representations = Vec::with_capacity(count.into());
+
+    for _ in 0..count {
+        let mime_len = u16::from_le_bytes(read_array(reader)?);
+        let mime = String::from_utf8(read_exactly(reader, mime_len.into())?)?;
+        if mime.is_empty() {
+            return Err(ProtocolError::EmptyMime);
+        }
+
+        let len = u64::from_le_bytes(read_array(reader)?);
+        total_len = total_len.saturating_add(len);
+        if total_len > MAX_PAYLOAD_LEN {
+            return Err(ProtocolError::TooLarge(total_len));
+        }
+
+        representations.push(Representation {
+            mime,
+            data: read_exactly(reader, len)?,
+        });
+    }
+
+    if reader.read(&mut [0])? != 0 {
+        return Err(ProtocolError::TrailingData);
+    }
+
+    Ok(representations)
+}
+
+/// Read exactly `N` bytes
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read exactly `len` bytes. Fails if the reader ends sooner
+fn read_exactly(reader: &mut impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 == len {
+        Ok(bytes)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {len} bytes, found {}", bytes.len()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Encode the representations into a frame
+    fn encode_frame(representations: &[Representation]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        encode(representations, &mut frame).expect("writing to a Vec succeeds");
+        frame
+    }
+
+    #[test]
+    fn round_trip() {
+        for representations in [
+            vec![Representation::new(
+                "text/plain;charset=utf-8",
+                "https://0x0.st/abcd.png",
+            )],
+            vec![Representation::new("text/plain;charset=utf-8", "")],
+            vec![
+                Representation::new("image/png", b"\x89PNG not really".to_vec()),
+                Representation::new("text/uri-list", "file:///tmp/a.png\r\n"),
+                Representation::new("text/plain;charset=utf-8", "/tmp/a.png"),
+            ],
+        ] {
+            assert_eq!(
+                decode(&mut encode_frame(&representations).as_slice()).ok(),
+                Some(representations.clone()),
+                "{representations:?} is decoded as it was encoded"
+            );
+        }
+    }
+
+    #[test]
+    fn frame_layout() {
+        let frame = encode_frame(&[
+            Representation::new("text/plain", "hi"),
+            Representation::new("a/b", ""),
+        ]);
+
+        let mut expected = b"FSCB\x02".to_vec();
+        expected.extend_from_slice(&2_u16.to_le_bytes());
+        expected.extend_from_slice(&10_u16.to_le_bytes());
+        expected.extend_from_slice(b"text/plain");
+        expected.extend_from_slice(&2_u64.to_le_bytes());
+        expected.extend_from_slice(b"hi");
+        expected.extend_from_slice(&3_u16.to_le_bytes());
+        expected.extend_from_slice(b"a/b");
+        expected.extend_from_slice(&0_u64.to_le_bytes());
+
+        assert_eq!(frame, expected, "layout documented in the module");
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let frame = encode_frame(&[Representation::new("image/png", vec![1, 2, 3, 4])]);
+
+        let decode = |frame: &[u8]| decode(&mut &frame[..]);
+
+        assert!(
+            matches!(decode(b"PNG\x00"), Err(ProtocolError::BadMagic)),
+            "garbage"
+        );
+        assert!(
+            matches!(decode(b""), Err(ProtocolError::Io(_))),
+            "empty input"
+        );
+        assert!(
+            matches!(decode(&encode_frame(&[])), Err(ProtocolError::Empty)),
+            "nothing to put into the clipboard"
+        );
+        assert!(
+            matches!(
+                decode(&encode_frame(&[Representation::new("", "hi")])),
+                Err(ProtocolError::EmptyMime)
+            ),
+            "empty MIME type"
+        );
+
+        let mut invalid = frame.clone();
+        invalid[4] = 1;
+        assert!(
+            matches!(decode(&invalid), Err(ProtocolError::UnsupportedVersion(1))),
+            "older version"
+        );
+
+        assert!(
+            matches!(decode(&frame[..frame.len() - 1]), Err(ProtocolError::Io(_))),
+            "truncated payload"
+        );
+
+        let mut invalid = frame.clone();
+        invalid.push(0);
+        assert!(
+            matches!(decode(&invalid), Err(ProtocolError::TrailingData)),
+            "data after the payload"
+        );
+
+        let mut invalid = frame;
+        let len_offset = invalid.len() - 4 - 8;
+        invalid[len_offset..len_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(
+            matches!(decode(&invalid), Err(ProtocolError::TooLarge(_))),
+            "corrupted length"
+        );
+    }
+}
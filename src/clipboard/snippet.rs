@@ -0,0 +1,103 @@
+//! Turn screenshots into snippets of text, to paste them into documents and issues
+
+use base64::Engine as _;
+
+/// Alt text of the image in the snippets
+const ALT: &str = "screenshot";
+
+/// The image as a `data:image/png;base64,...` URI, which contains the whole image
+pub fn data_uri(image: &image::DynamicImage) -> Result<String, image::ImageError> {
+    let png = crate::image_upload::EncodedImage::png(image)?;
+
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png.bytes)
+    ))
+}
+
+/// Markdown image which shows the image at `src`, a link or a path
+pub fn markdown(src: &str) -> String {
+    // backslashes of Windows paths would escape the character after them
+    let src = src.replace('\\', "\\\\");
+
+    // a destination with spaces or parentheses must be enclosed in angle brackets,
+    // inside of which angle brackets are escaped with a backslash
+    if src.contains([' ', '(', ')', '<', '>']) {
+        format!(
+            "![{ALT}](<{}>)",
+            src.replace('<', "\\<").replace('>', "\\>")
+        )
+    } else {
+        format!("![{ALT}]({src})")
+    }
+}
+
+/// HTML `<img>` tag which shows the `width` by `height` image at `src`
+pub fn html(src: &str, (width, height): (u32, u32)) -> String {
+    let src = src
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    format!(r#"<img src="{src}" alt="{ALT}" width="{width}" height="{height}">"#)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_uri_contains_png() {
+        let image = image::DynamicImage::new_rgba8(2, 2);
+        let uri = data_uri(&image).expect("encode the image");
+
+        let encoded = uri
+            .strip_prefix("data:image/png;base64,")
+            .expect("PNG data URI");
+        let png = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .expect("valid base64");
+
+        assert_eq!(
+            image::load_from_memory(&png)
+                .map(|image| (image.width(), image.height()))
+                .ok(),
+            Some((2, 2)),
+            "the URI contains the image"
+        );
+    }
+
+    #[test]
+    fn markdown_destinations() {
+        assert_eq!(
+            markdown("https://0x0.st/abcd.png"),
+            "![screenshot](https://0x0.st/abcd.png)",
+            "link"
+        );
+        assert_eq!(
+            markdown("/home/me/Pictures/my screenshot (1).png"),
+            "![screenshot](</home/me/Pictures/my screenshot (1).png>)",
+            "path with spaces and parentheses"
+        );
+        assert_eq!(
+            markdown("/tmp/<draft> (1).png"),
+            r"![screenshot](</tmp/\<draft\> (1).png>)",
+            "angle brackets are escaped, so the path stays the same"
+        );
+        assert_eq!(
+            markdown(r"C:\Users\me\my screenshot.png"),
+            r"![screenshot](<C:\\Users\\me\\my screenshot.png>)",
+            "backslashes are escaped"
+        );
+    }
+
+    #[test]
+    fn html_escapes() {
+        assert_eq!(
+            html("https://example.com/a.png?x=1&y=\"2\"", (800, 600)),
+            r#"<img src="https://example.com/a.png?x=1&amp;y=&quot;2&quot;" alt="screenshot" width="800" height="600">"#,
+            "attribute is escaped"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Serve the clipboard on Wayland, through the data control protocol of the compositor
+
+use wl_clipboard_rs::copy::{MimeSource, MimeType, Options, Source};
+
+use super::protocol::Representation;
+
+/// Offer every representation, until something else is copied
+pub fn serve(representations: &[Representation]) -> Result<(), wl_clipboard_rs::copy::Error> {
+    let sources = representations
+        .iter()
+        .flat_map(|representation| {
+            super::mime_aliases(&representation.mime).map(|mime| MimeSource {
+                source: Source::Bytes(representation.data.clone().into_boxed_slice()),
+                mime_type: MimeType::Specific(mime.to_string()),
+            })
+        })
+        .collect();
+
+    let mut options = Options::new();
+    // we already are the daemon, so there's no need to fork again
+    options.foreground(true);
+    options.copy_multi(sources)
+}
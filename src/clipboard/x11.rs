@@ -0,0 +1,282 @@
+//! Serve the clipboard on X11, by owning the `CLIPBOARD` selection
+
+use std::collections::HashMap;
+
+use x11rb::{
+    CURRENT_TIME, NONE,
+    connection::Connection,
+    errors::{ConnectError, ReplyOrIdError},
+    protocol::{
+        Event,
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, CreateWindowAux,
+            EventMask, PropMode, Property, SELECTION_NOTIFY_EVENT, SelectionNotifyEvent,
+            SelectionRequestEvent, Window, WindowClass,
+        },
+    },
+    wrapper::ConnectionExt as _,
+};
+
+use super::protocol::Representation;
+
+/// Most bytes sent at once when a payload is sent in chunks
+const INCR_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Could not serve the clipboard
+#[derive(thiserror::Error, Debug)]
+pub enum X11Error {
+    /// Could not connect to the X server
+    #[error("Could not connect to the X server: {0}")]
+    Connect(#[from] ConnectError),
+    /// The connection to the X server failed
+    #[error("X11 error: {0}")]
+    Connection(#[from] ReplyOrIdError),
+    /// Another application became the owner of the clipboard before us
+    #[error("Could not take ownership of the clipboard")]
+    NotOwner,
+}
+
+/// A payload which is too large for a single request, so it is sent in chunks
+/// with the `INCR` protocol. The next chunk is sent once the requestor deleted the previous one
+#[derive(Debug)]
+struct Transfer<'a> {
+    /// Type of the payload
+    target: Atom,
+    /// What is left to send
+    remaining: &'a [u8],
+}
+
+/// Offer every representation, until something else is copied and
+/// every payload which is being sent in chunks was sent
+pub fn serve(representations: &[Representation]) -> Result<(), X11Error> {
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let screen = &conn.setup().roots[screen_num];
+
+    // the selection must be owned by a window, which is never shown
+    let window = conn.generate_id()?;
+    conn.create_window(
+        x11rb::COPY_DEPTH_FROM_PARENT,
+        window,
+        screen.root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        screen.root_visual,
+        &CreateWindowAux::new(),
+    )
+    .map_err(ReplyOrIdError::from)?;
+
+    let intern = |name: &str| -> Result<Atom, ReplyOrIdError> {
+        Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    };
+
+    let atoms = Atoms {
+        clipboard: intern("CLIPBOARD")?,
+        targets: intern("TARGETS")?,
+        incr: intern("INCR")?,
+    };
+
+    let mut offers = Vec::new();
+    for representation in representations {
+        for mime in super::mime_aliases(&representation.mime) {
+            offers.push((intern(mime)?, representation.data.as_slice()));
+        }
+    }
+
+    conn.set_selection_owner(window, atoms.clipboard, CURRENT_TIME)
+        .map_err(ReplyOrIdError::from)?;
+    let owner = conn
+        .get_selection_owner(atoms.clipboard)
+        .map_err(ReplyOrIdError::from)?
+        .reply()
+        .map_err(ReplyOrIdError::from)?
+        .owner;
+    if owner != window {
+        return Err(X11Error::NotOwner);
+    }
+    conn.flush().map_err(ReplyOrIdError::from)?;
+
+    // keyed by the requesting window and the property the payload is stored in
+    let mut transfers = HashMap::<(Window, Atom), Transfer<'_>>::new();
+    let mut is_owner = true;
+
+    while is_owner || !transfers.is_empty() {
+        match conn.wait_for_event().map_err(ReplyOrIdError::from)? {
+            Event::SelectionRequest(request) if is_owner => {
+                if let Some((property, transfer)) = reply(&conn, &request, &atoms, &offers)? {
+                    transfers.insert((request.requestor, property), transfer);
+                }
+            }
+            // something else was copied, but pastes which already started are finished
+            Event::SelectionClear(event) if event.selection == atoms.clipboard => {
+                is_owner = false;
+            }
+            // the requestor deleted the previous chunk, so it's ready for the next one
+            Event::PropertyNotify(event) if event.state == Property::DELETE => {
+                send_chunk(&conn, &mut transfers, (event.window, event.atom))?;
+            }
+            // the requestor is gone, so it won't ask for the rest of the payload
+            Event::DestroyNotify(event) => {
+                transfers.retain(|(requestor, _), _| *requestor != event.window);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Atoms used to serve the clipboard
+#[derive(Debug, Clone, Copy)]
+struct Atoms {
+    /// The selection which is the clipboard
+    clipboard: Atom,
+    /// Asks for the list of types we offer
+    targets: Atom,
+    /// Type of a payload which is sent in chunks
+    incr: Atom,
+}
+
+/// Send the next chunk of the payload stored in the `property` of the `requestor`,
+/// if a payload is being sent there
+fn send_chunk(
+    conn: &impl Connection,
+    transfers: &mut HashMap<(Window, Atom), Transfer<'_>>,
+    (requestor, property): (Window, Atom),
+) -> Result<(), ReplyOrIdError> {
+    let Some(transfer) = transfers.get_mut(&(requestor, property)) else {
+        return Ok(());
+    };
+
+    let chunk_size = INCR_CHUNK_SIZE.min(conn.maximum_request_bytes() - 64);
+    let (chunk, remaining) = transfer
+        .remaining
+        .split_at(chunk_size.min(transfer.remaining.len()));
+
+    // an empty chunk marks the end of the payload
+    conn.change_property8(
+        PropMode::REPLACE,
+        requestor,
+        property,
+        transfer.target,
+        chunk,
+    )?;
+
+    if chunk.is_empty() {
+        transfers.remove(&(requestor, property));
+        stop_watching(conn, requestor, transfers)?;
+    } else {
+        transfer.remaining = remaining;
+    }
+    conn.flush()?;
+
+    Ok(())
+}
+
+/// Stop receiving events of the `requestor` once none of its payloads are being sent
+fn stop_watching(
+    conn: &impl Connection,
+    requestor: Window,
+    transfers: &HashMap<(Window, Atom), Transfer<'_>>,
+) -> Result<(), ReplyOrIdError> {
+    if !transfers.keys().any(|(window, _)| *window == requestor) {
+        conn.change_window_attributes(
+            requestor,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+        )?;
+    }
+    Ok(())
+}
+
+/// Store the requested representation in the property of the requesting window,
+/// and tell it whether we could
+///
+/// # Returns
+///
+/// The property and the payload to send in chunks, if it is too large for a single request
+fn reply<'a>(
+    conn: &impl Connection,
+    request: &SelectionRequestEvent,
+    atoms: &Atoms,
+    offers: &[(Atom, &'a [u8])],
+) -> Result<Option<(Atom, Transfer<'a>)>, ReplyOrIdError> {
+    // obsolete clients don't say where to store the content
+    let property = if request.property == NONE {
+        request.target
+    } else {
+        request.property
+    };
+
+    let mut transfer = None;
+
+    let stored = if request.target == atoms.targets {
+        let atoms = std::iter::once(atoms.targets)
+            .chain(offers.iter().map(|(atom, _)| *atom))
+            .collect::<Vec<_>>();
+        conn.change_property32(
+            PropMode::REPLACE,
+            request.requestor,
+            property,
+            AtomEnum::ATOM,
+            &atoms,
+        )?;
+        true
+    } else if let Some((_, data)) = offers.iter().find(|(atom, _)| *atom == request.target) {
+        // leave room for the header of the request
+        if data.len() + 64 > conn.maximum_request_bytes() {
+            // the requestor deletes the property to ask for the first chunk
+            conn.change_window_attributes(
+                request.requestor,
+                &ChangeWindowAttributesAux::new()
+                    .event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY),
+            )?;
+            conn.change_property32(
+                PropMode::REPLACE,
+                request.requestor,
+                property,
+                atoms.incr,
+                &[u32::try_from(data.len()).unwrap_or(u32::MAX)],
+            )?;
+            transfer = Some((
+                property,
+                Transfer {
+                    target: request.target,
+                    remaining: data,
+                },
+            ));
+            true
+        } else {
+            conn.change_property8(
+                PropMode::REPLACE,
+                request.requestor,
+                property,
+                request.target,
+                data,
+            )?;
+            true
+        }
+    } else {
+        false
+    };
+
+    conn.send_event(
+        false,
+        request.requestor,
+        EventMask::NO_EVENT,
+        SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if stored { property } else { NONE },
+        },
+    )?;
+    conn.flush()?;
+
+    Ok(transfer)
+}
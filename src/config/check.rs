@@ -0,0 +1,269 @@
+//! Check the config without launching the app, with `ferrishot --check-config`
+//!
+//! Besides the errors which prevent the config from loading, this finds keybindings that
+//! can never be triggered, because the same keys are bound twice in the same file.
+//! Only the last binding is used. It also reports fallback upload providers which don't exist,
+//! as they are skipped when uploading.
+//!
+//! Binding the same keys as the default config is not a problem, as it's how defaults are overridden.
+
+use std::collections::HashMap;
+
+use miette::{IntoDiagnostic as _, NamedSource, SourceSpan, miette};
+
+use super::{
+    DEFAULT_CONFIG_NAME, DEFAULT_KDL_CONFIG_STR,
+    key::{KeyMods, KeySequence},
+    options::Key,
+};
+
+/// A keybinding is overridden by a later one for the same keys, in the same file
+#[derive(thiserror::Error, miette::Diagnostic, Debug)]
+#[error("{message}")]
+#[diagnostic(
+    severity(Warning),
+    help(
+        "Only the last keybinding for the same keys is used. Remove one of them, or bind it to other keys"
+    )
+)]
+pub struct KeyConflict {
+    /// What is wrong
+    message: String,
+    /// Contents of the config file
+    #[source_code]
+    source_code: NamedSource<String>,
+    /// The keybinding which is never triggered
+    #[label("never used")]
+    span: SourceSpan,
+    /// The keybinding which overrides it
+    #[label("overrides it")]
+    other_span: SourceSpan,
+}
+
+/// Every keybinding which can never be triggered
+#[derive(thiserror::Error, miette::Diagnostic, Debug)]
+#[error("Found {} keybindings which can never be triggered", conflicts.len())]
+pub struct KeyConflicts {
+    /// Each of the conflicts
+    #[related]
+    conflicts: Vec<KeyConflict>,
+}
+
+/// A config file, used to point at its keybindings
+struct ConfigFile {
+    /// Name of the file
+    name: String,
+    /// Contents of the file
+    text: String,
+}
+
+impl ConfigFile {
+    /// The keybinding as written in the file, on a single line
+    fn snippet(&self, span: SourceSpan) -> String {
+        self.text
+            .get(span.offset()..span.offset() + span.len())
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A keybinding, and where it was declared
+struct Binding<'a> {
+    /// File which declares the keybinding
+    file: &'a ConfigFile,
+    /// Where in the `file`
+    span: SourceSpan,
+    /// Keys which trigger the keybinding
+    keys: (KeySequence, KeyMods),
+}
+
+impl<'a> Binding<'a> {
+    /// The keybinding declared in the file
+    fn new(file: &'a ConfigFile, key: &knus::span::Spanned<Key, knus::span::Span>) -> Self {
+        Self {
+            file,
+            span: key.span().clone().into(),
+            keys: (**key).clone().action().0,
+        }
+    }
+
+    /// The keybinding as written in its file
+    fn snippet(&self) -> String {
+        self.file.snippet(self.span)
+    }
+
+    /// `earlier` is never used, as `self` binds the same keys after it in the same file
+    fn overrides(&self, earlier: &Self) -> KeyConflict {
+        KeyConflict {
+            message: format!(
+                "`{}` is overridden by `{}`",
+                earlier.snippet(),
+                self.snippet()
+            ),
+            source_code: NamedSource::new(&self.file.name, self.file.text.clone()),
+            span: earlier.span,
+            other_span: self.span,
+        }
+    }
+}
+
+/// Every keybinding which can never be triggered, in the order they were declared
+fn find_conflicts(bindings: &[Binding]) -> Vec<KeyConflict> {
+    let mut last_in_file = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for binding in bindings {
+        if let Some(earlier) =
+            last_in_file.insert((binding.file.name.as_str(), &binding.keys), binding)
+        {
+            conflicts.push(binding.overrides(earlier));
+        }
+    }
+
+    conflicts
+}
+
+/// Check the config file, merged with the default config. Fails if it can't be loaded,
+/// if some keybindings can never be triggered, or if a fallback upload provider does not exist
+pub fn check_config(config_file: &str) -> miette::Result<()> {
+    let (default_config, user_config) = super::parse(config_file)?;
+
+    let default_file = ConfigFile {
+        name: DEFAULT_CONFIG_NAME.to_string(),
+        text: DEFAULT_KDL_CONFIG_STR.to_string(),
+    };
+    let user_file = ConfigFile {
+        name: config_file.to_string(),
+        text: std::fs::read_to_string(config_file).unwrap_or_default(),
+    };
+
+    let bindings = default_config
+        .keys
+        .keys
+        .iter()
+        .map(|key| Binding::new(&default_file, key))
+        .chain(
+            user_config
+                .keys
+                .iter()
+                .flat_map(|keys| &keys.keys)
+                .map(|key| Binding::new(&user_file, key)),
+        )
+        .collect::<Vec<_>>();
+    let conflicts = find_conflicts(&bindings);
+
+    let config = super::build(default_config.merge_user_config(user_config), config_file)?;
+    config.upload_providers_in_order().into_diagnostic()?;
+
+    let unknown_fallbacks = config
+        .unknown_fallback_upload_providers()
+        .collect::<Vec<_>>();
+    if !unknown_fallbacks.is_empty() {
+        return Err(miette!(
+            help = "Define an upload provider with this name, or remove it from `upload-providers { fallback }`",
+            "Unknown fallback upload providers: {}",
+            unknown_fallbacks.join(", ")
+        ));
+    }
+
+    if conflicts.is_empty() {
+        println!("{config_file} is valid");
+        Ok(())
+    } else {
+        Err(KeyConflicts { conflicts }.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::options::{DefaultKdlConfig, UserKdlConfig};
+
+    /// Messages of the conflicts between the keybindings of the default and the user config
+    fn conflicts(default: &str, user: &str) -> Vec<String> {
+        let default_file = ConfigFile {
+            name: DEFAULT_CONFIG_NAME.to_string(),
+            text: default.to_string(),
+        };
+        let user_file = ConfigFile {
+            name: String::from("config.kdl"),
+            text: user.to_string(),
+        };
+
+        let parse = |file: &ConfigFile| {
+            knus::parse::<UserKdlConfig>(&file.name, &file.text)
+                .expect("valid config")
+                .keys
+                .map(|keys| keys.keys)
+                .unwrap_or_default()
+        };
+        let default_keys = parse(&default_file);
+        let user_keys = parse(&user_file);
+
+        let bindings = default_keys
+            .iter()
+            .map(|key| Binding::new(&default_file, key))
+            .chain(user_keys.iter().map(|key| Binding::new(&user_file, key)))
+            .collect::<Vec<_>>();
+
+        find_conflicts(&bindings)
+            .into_iter()
+            .map(|conflict| conflict.message)
+            .collect()
+    }
+
+    #[test]
+    fn default_config_has_no_conflicts() {
+        let default_config =
+            knus::parse::<DefaultKdlConfig>(DEFAULT_CONFIG_NAME, DEFAULT_KDL_CONFIG_STR)
+                .expect("the default config is valid");
+        let default_file = ConfigFile {
+            name: DEFAULT_CONFIG_NAME.to_string(),
+            text: DEFAULT_KDL_CONFIG_STR.to_string(),
+        };
+        let bindings = default_config
+            .keys
+            .keys
+            .iter()
+            .map(|key| Binding::new(&default_file, key))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            find_conflicts(&bindings)
+                .into_iter()
+                .map(|conflict| conflict.message)
+                .collect::<Vec<_>>(),
+            Vec::<String>::new(),
+            "every default keybinding can be triggered"
+        );
+    }
+
+    #[test]
+    fn duplicate_keybindings() {
+        assert_eq!(
+            conflicts(
+                "keys {\n  move left 1 key=h\n}",
+                "keys {\n  goto center key=h\n}"
+            ),
+            Vec::<String>::new(),
+            "overriding a default keybinding is fine"
+        );
+
+        assert_eq!(
+            conflicts(
+                "",
+                "keys {\n  move left 1 key=<left>\n  extend left 1 key=<left>\n  exit mod=ctrl key=<left>\n}"
+            ),
+            ["`move left 1 key=<left>` is overridden by `extend left 1 key=<left>`"],
+            "same keys in the same file, but not with other modifiers"
+        );
+
+        assert_eq!(
+            conflicts("", "keys {\n  exit key=g\n  goto top-left key=gg\n}"),
+            Vec::<String>::new(),
+            "a key can be bound on its own and as the start of a sequence"
+        );
+    }
+}
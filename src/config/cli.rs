@@ -1,13 +1,33 @@
 //! Parse the command line arguments passed to ferrishot
-use std::{path::PathBuf, sync::LazyLock};
+use std::{path::PathBuf, str::FromStr, sync::LazyLock};
 
 use clap::Parser;
+
+use crate::image_format::ImageFormat;
+use crate::report::ResultFormat;
 use etcetera::BaseStrategy;
 
 /// Command line arguments for the program
 #[derive(Parser, Debug)]
-#[command(version, about, author = "Nik Revenco")]
+#[command(
+    version,
+    about,
+    author = "Nik Revenco",
+    args_conflicts_with_subcommands = true
+)]
 pub struct Cli {
+    /// Do something other than taking a screenshot
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Open this image instead of taking a screenshot. Use `-` to read from standard input
+    #[arg(value_name = "image")]
+    pub image: Option<Input>,
+    /// Same as passing the image as a positional argument
+    #[arg(long, value_name = "image", conflicts_with = "image")]
+    pub input: Option<Input>,
+    /// Browse the history of recent captures, to copy, save, upload or delete them
+    #[arg(long, conflicts_with_all = ["image", "input", "region", "output"])]
+    pub history: bool,
     /// Write the default config file
     #[arg(long, help = format!("Write the default config to {}", DEFAULT_CONFIG_FILE_PATH.display()))]
     pub dump_default_config: bool,
@@ -18,6 +38,169 @@ pub struct Cli {
         default_value_t = DEFAULT_CONFIG_FILE_PATH.to_string_lossy().to_string()
     )]
     pub config_file: String,
+    /// Check the config file for errors and keybindings which can never be triggered, then exit
+    #[arg(long)]
+    pub check_config: bool,
+    /// Print every keybinding, after merging the config file with the default config, then exit
+    #[arg(long)]
+    pub print_keymap: bool,
+    /// Wait this many seconds before taking the screenshot
+    ///
+    /// Overrides the `delay` config option
+    #[arg(long, value_name = "seconds")]
+    pub delay: Option<u32>,
+    /// Capture this region of the screen without opening the app, then exit
+    ///
+    /// The region is relative to the top-left corner of the desktop, or
+    /// of the monitor if `--monitor` is passed.
+    #[arg(long, value_name = "x,y,width,height", requires = "output")]
+    pub region: Option<Region>,
+    /// Name or index (starting at 0) of the monitor which `--region` is relative to
+    #[arg(long, value_name = "name", requires = "region")]
+    pub monitor: Option<String>,
+    /// Format of the saved screenshot, if it cannot be determined from the file extension
+    ///
+    /// Overrides the `format` config option
+    #[arg(long, value_enum)]
+    pub format: Option<ImageFormat>,
+    /// Write the screenshot to this file. Use `-` to write to standard output
+    ///
+    /// Without `--region`, the screenshot is written once you copy or save the selection,
+    /// instead of copying it to the clipboard or opening the file dialog
+    #[arg(short, long, value_name = "file")]
+    pub output: Option<Output>,
+    /// Print a summary of what happened when ferrishot exits: the action taken,
+    /// the selected rectangle, where the screenshot was saved and the link to the uploaded image
+    ///
+    /// Printed to standard error when the screenshot is written to standard output
+    #[arg(long, value_enum, value_name = "format")]
+    pub print_result: Option<ResultFormat>,
+}
+
+impl Cli {
+    /// The image to open instead of taking a screenshot, if any
+    pub fn input(&self) -> Option<&Input> {
+        self.image.as_ref().or(self.input.as_ref())
+    }
+
+    /// Whether the screenshot is written to standard output
+    pub fn writes_to_stdout(&self) -> bool {
+        matches!(self.output, Some(Output::Stdout))
+    }
+}
+
+/// Subcommands of ferrishot
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Manage the history of uploaded images
+    Uploads {
+        /// What to do with the uploads
+        #[command(subcommand)]
+        command: UploadsCommand,
+    },
+}
+
+/// What to do with the history of uploaded images
+#[derive(clap::Subcommand, Debug)]
+pub enum UploadsCommand {
+    /// List uploaded images, oldest first
+    ///
+    /// Each line contains the ID, date, upload provider, size and link of the image, and
+    /// where a copy of it is stored (`-` if nowhere), separated by tabs
+    List,
+    /// Delete an uploaded image from the internet, using the deletion link returned by its provider
+    Delete {
+        /// ID of the upload, as shown by `ferrishot uploads list`
+        id: String,
+    },
+}
+
+/// A rectangular region of the screen, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// x-coordinate of the top-left corner
+    pub x: u32,
+    /// y-coordinate of the top-left corner
+    pub y: u32,
+    /// Width of the region
+    pub width: u32,
+    /// Height of the region
+    pub height: u32,
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .parse::<u32>()
+                    .map_err(|err| format!("Invalid number `{part}`: {err}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [x, y, width, height] = parts[..] else {
+            return Err(format!(
+                "Expected 4 numbers separated by commas: `x,y,width,height`. Got {} instead",
+                parts.len()
+            ));
+        };
+
+        if width == 0 || height == 0 {
+            return Err(String::from("The width and height must be larger than 0"));
+        }
+
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// Where to read an image from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// Standard input, specified as `-`
+    Stdin,
+    /// A file at the given path
+    File(PathBuf),
+}
+
+impl FromStr for Input {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "-" {
+            Self::Stdin
+        } else {
+            Self::File(PathBuf::from(s))
+        })
+    }
+}
+
+/// Where to write the screenshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// Standard output, specified as `-`
+    Stdout,
+    /// A file at the given path
+    File(PathBuf),
+}
+
+impl FromStr for Output {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "-" {
+            Self::Stdout
+        } else {
+            Self::File(PathBuf::from(s))
+        })
+    }
 }
 
 /// Represents the default location of the config file
@@ -36,3 +219,48 @@ static DEFAULT_CONFIG_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
 /// It is a static because it is needed by the `CONFIG` static, in order to
 /// read config from the correct place
 pub static CLI: LazyLock<Cli> = LazyLock::new(Cli::parse);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_region() {
+        assert_eq!(
+            "10,20,300,400".parse::<Region>(),
+            Ok(Region {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 400
+            }),
+            "a valid region"
+        );
+        assert_eq!(
+            " 0, 0, 1, 1 ".parse::<Region>(),
+            Ok(Region {
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1
+            }),
+            "whitespace around numbers is allowed"
+        );
+        assert_eq!(
+            "10,20,300".parse::<Region>(),
+            Err(String::from(
+                "Expected 4 numbers separated by commas: `x,y,width,height`. Got 3 instead"
+            )),
+            "too few numbers"
+        );
+        assert_eq!(
+            "10,20,0,400".parse::<Region>(),
+            Err(String::from("The width and height must be larger than 0")),
+            "empty regions are invalid"
+        );
+        assert!(
+            "-1,0,1,1".parse::<Region>().is_err(),
+            "negative numbers are invalid"
+        );
+    }
+}
@@ -12,69 +12,362 @@ use crate::config::Key;
 
 use super::KeyAction;
 
+/// A key which was pressed, and the modifiers which were held down. Shift is never one of the modifiers
+pub type KeyPress = (Input, KeyMods);
+
 /// Represents the keybindings for ferrishot
+///
+/// It's a trie: every node is reached by pressing the keys on the path to it, and the
+/// keybinding of those keys is the `action` of the node. A node can both have an action
+/// and be the start of longer sequences, e.g. when `g` and `gg` are both bound.
+/// In which case pressing `g` waits for the next key, and `g` is only triggered if the next
+/// key does not continue the sequence, or after the `key-timeout`.
+///
+/// The modifiers of a keybinding must be held for each key of its sequence.
 #[derive(Debug, Default)]
 pub struct KeyMap {
-    /// Map of Key Pressed => Action when pressing that key
-    pub keys: HashMap<(KeySequence, KeyMods), KeyAction>,
+    /// What happens when the keys leading to this node are pressed
+    action: Option<KeyAction>,
+    /// Keys which continue the sequence
+    children: HashMap<KeyPress, KeyMap>,
 }
 
 impl KeyMap {
-    /// Get a key from the `KeyMap`
-    ///
-    /// So why do we need owned values here? `.get()` methods returning a reference usually
-    /// do not require owned values
-    ///
-    /// If we had an `&IcedKey` and an `Option<&IcedKey>` we won't be able to turn that into
-    /// an `&KeySequence` without cloning unfortunately.
-    ///
-    /// This is a well-known problem in Rust: <https://stackoverflow.com/questions/45786717/how-to-get-value-from-hashmap-with-two-keys-via-references-to-both-keys/45795699#45795699>
-    ///
-    /// The solution outlined in the Stack Overflow post, while fun, is not zero-cost and
-    /// adds too much unnecessary complexity whilst probably being slower. (We have to go
-    /// through the V-Table lookup, as it requires using dynamic dispatch)
+    /// Bind the keys to the action. Replaces the action which was bound to the same keys
+    pub fn insert(&mut self, (sequence, mods): (KeySequence, KeyMods), action: KeyAction) {
+        let node = sequence.0.into_iter().fold(self, |node, key| {
+            node.children.entry((key, mods.clone())).or_default()
+        });
+        node.action = Some(action);
+    }
+
+    /// Remove the keybinding of the keys, and the nodes which no longer lead to any keybinding
+    pub fn remove(&mut self, keys: &[KeyPress]) {
+        let Some((key, rest)) = keys.split_first() else {
+            self.action = None;
+            return;
+        };
+
+        if let Some(node) = self.children.get_mut(key) {
+            node.remove(rest);
+            if node.action.is_none() && node.children.is_empty() {
+                self.children.remove(key);
+            }
+        }
+    }
+
+    /// Every keybinding, sorted by their keys
+    pub fn bindings(&self) -> Vec<((KeySequence, KeyMods), &KeyAction)> {
+        /// Add the keybindings of the `node`, reached by pressing the `keys`
+        fn collect<'a>(
+            node: &'a KeyMap,
+            keys: &mut Vec<KeyPress>,
+            bindings: &mut Vec<((KeySequence, KeyMods), &'a KeyAction)>,
+        ) {
+            if let Some(action) = &node.action {
+                // every key of a sequence has the modifiers of the keybinding
+                let mods = keys
+                    .first()
+                    .map(|(_, mods)| mods.clone())
+                    .unwrap_or_default();
+                let sequence = KeySequence(keys.iter().map(|(key, _)| key.clone()).collect());
+                bindings.push(((sequence, mods), action));
+            }
+            for (key, child) in &node.children {
+                keys.push(key.clone());
+                collect(child, keys, bindings);
+                keys.pop();
+            }
+        }
+
+        let mut bindings = Vec::new();
+        collect(self, &mut Vec::new(), &mut bindings);
+        bindings
+            .sort_by_cached_key(|((sequence, mods), _)| (sequence.to_string(), mods.to_string()));
+        bindings
+    }
+
+    /// The node reached by pressing the keys, if they are the start of a keybinding
+    fn get(&self, keys: &[KeyPress]) -> Option<&Self> {
+        keys.iter()
+            .try_fold(self, |node, key| node.children.get(key))
+    }
+
+    /// Press the `key` after the `pending` keys, which were pressed before it
+    /// but did not trigger anything yet. Returns the actions to execute
     ///
-    pub fn get(
-        &self,
-        key: IcedKey,
-        previous_key: Option<IcedKey>,
-        mods: Modifiers,
-    ) -> Option<&KeyAction> {
-        self.keys
-            .get(&(KeySequence((key, previous_key)), KeyMods(mods)))
+    /// Afterwards, `pending` contains the keys which are the start of a longer sequence,
+    /// which we wait for the next key of. If nothing else is pressed, `timeout` must be called.
+    pub fn press(&self, pending: &mut Vec<KeyPress>, key: KeyPress) -> Vec<KeyAction> {
+        match self.get(pending).and_then(|node| node.children.get(&key)) {
+            // a longer sequence starts with these keys, so wait for the next one
+            Some(node) if !node.children.is_empty() => {
+                pending.push(key);
+                Vec::new()
+            }
+            Some(node) => {
+                pending.clear();
+                node.action.clone().into_iter().collect()
+            }
+            None if pending.is_empty() => Vec::new(),
+            // The key interrupted the sequence. The pending keys may be a keybinding
+            // on their own, and the key may be the start of another one.
+            //
+            // For example with `g` and `gg` bound, pressing `gj` triggers `g` then `j`
+            None => {
+                let mut actions = self.timeout(pending).into_iter().collect::<Vec<_>>();
+                actions.extend(self.press(pending, key));
+                actions
+            }
+        }
+    }
+
+    /// Whether pressing the `key` after the `pending` keys continues their sequence,
+    /// instead of interrupting it
+    pub fn continues(&self, pending: &[KeyPress], key: &KeyPress) -> bool {
+        self.get(pending)
+            .is_some_and(|node| node.children.contains_key(key))
+    }
+
+    /// Keys which continue the `pending` keys, sorted by their name
+    pub fn continuations(&self, pending: &[KeyPress]) -> Vec<Continuation<'_>> {
+        let mut continuations = self
+            .get(pending)
+            .map(|node| {
+                node.children
+                    .iter()
+                    .map(|(key, node)| Continuation {
+                        key,
+                        action: node.action.as_ref(),
+                        longer: node.children.values().map(Self::len).sum(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        continuations.sort_by_cached_key(|continuation| key_press_name(continuation.key));
+        continuations
+    }
+
+    /// Amount of keybindings which start with the keys leading to this node
+    fn len(&self) -> usize {
+        usize::from(self.action.is_some()) + self.children.values().map(Self::len).sum::<usize>()
+    }
+
+    /// No key was pressed after the `pending` keys for a while, so stop waiting for
+    /// the rest of the sequence. Returns the action bound to the pending keys, if any
+    pub fn timeout(&self, pending: &mut Vec<KeyPress>) -> Option<KeyAction> {
+        let keys = std::mem::take(pending);
+        self.get(&keys).and_then(|node| node.action.clone())
+    }
+}
+
+/// The keybindings as they are written in the config, inside of a `keys` node
+impl std::fmt::Display for KeyMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "keys {{")?;
+        for ((sequence, mods), action) in self.bindings() {
+            write!(f, "  {action}")?;
+            if !mods.0.is_empty() {
+                write!(f, " mod={mods}")?;
+            }
+            writeln!(f, " key={:?}", sequence.to_string())?;
+        }
+        writeln!(f, "}}")
     }
 }
 
+/// A key which continues a key sequence
+#[derive(Debug, Clone, Copy)]
+pub struct Continuation<'a> {
+    /// The key to press next
+    pub key: &'a KeyPress,
+    /// What happens when it is pressed, if anything
+    pub action: Option<&'a KeyAction>,
+    /// Amount of longer keybindings which start with the key
+    pub longer: usize,
+}
+
 /// Keybindings for ferrishot
 #[derive(knus::Decode, Debug, Default)]
+#[knus(span_type = knus::span::Span)]
 pub struct Keys {
-    /// A list of raw keybindings for ferrishot, directly as read from the config file
+    /// Start from an empty `KeyMap` instead of the keybindings of the default config
+    #[knus(default, property(name = "clear-defaults"))]
+    pub clear_defaults: bool,
+    /// A list of raw keybindings for ferrishot, directly as read from the config file.
+    /// The span of each one is kept to point at conflicting keybindings
     #[knus(children)]
-    pub keys: Vec<Key>,
+    pub keys: Vec<knus::span::Spanned<Key, knus::span::Span>>,
 }
 
 impl FromIterator<Key> for KeyMap {
     fn from_iter<T: IntoIterator<Item = Key>>(iter: T) -> Self {
-        Self {
-            keys: iter.into_iter().map(Key::action).collect(),
+        let mut key_map = Self::default();
+        for key in iter {
+            match key.action() {
+                ((sequence, mods), KeyAction::Unbind) => key_map.remove(
+                    &sequence
+                        .0
+                        .into_iter()
+                        .map(|key| (key, mods.clone()))
+                        .collect::<Vec<_>>(),
+                ),
+                (keys, action) => key_map.insert(keys, action),
+            }
+        }
+        key_map
+    }
+}
+
+/// Something which is pressed as part of a keybinding
+#[derive(Debug, Hash, PartialEq, PartialOrd, Ord, Eq, Clone)]
+pub enum Input {
+    /// A key of the keyboard
+    Key(IcedKey),
+    /// A button of the mouse, or the scroll wheel
+    Mouse(MouseInput),
+}
+
+impl Input {
+    /// The input written as `<name>` in the config, e.g. `<space>` or `<mouse-right>`
+    fn from_name(name: &str) -> Result<Self, String> {
+        if name == "mouse-left" {
+            return Err(String::from(
+                "The left mouse button can't be bound, as it is used to select",
+            ));
         }
+
+        Named::from_str(name)
+            .map(|named| Self::Key(IcedKey::Named(named.to_iced())))
+            .or_else(|err| MouseInput::from_str(name).map(Self::Mouse).map_err(|_| err))
+            .map_err(|err| format!("Invalid key: <{name}>. {err}"))
     }
 }
 
-/// A sequence of 2 keys. If there are 2 keys like so:
-/// - (T, None)
-/// - (T, Some(X))
+/// Buttons of the mouse, and directions of the scroll wheel, which can be bound to actions
 ///
-/// The 2nd key will never be triggered.
-/// We will first search the `HashMap` of keys for the first key.
-/// If it does not exist, search for the 2nd key.
+/// The left button is not one of them, as it always creates, resizes and moves the selection
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, strum::EnumString, strum::Display,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MouseInput {
+    /// Right mouse button
+    MouseRight,
+    /// Middle mouse button, usually the scroll wheel being pressed
+    MouseMiddle,
+    /// Side button for going back
+    MouseBack,
+    /// Side button for going forward
+    MouseForward,
+    /// Scroll wheel moved up
+    ScrollUp,
+    /// Scroll wheel moved down
+    ScrollDown,
+    /// Scroll wheel moved left
+    ScrollLeft,
+    /// Scroll wheel moved right
+    ScrollRight,
+}
+
+impl MouseInput {
+    /// Whether this is a button, which is held down until it is released. Scrolling is not
+    pub const fn is_button(self) -> bool {
+        matches!(
+            self,
+            Self::MouseRight | Self::MouseMiddle | Self::MouseBack | Self::MouseForward
+        )
+    }
+
+    /// The mouse button which was pressed, unless it can't be bound
+    pub const fn from_button(button: iced::mouse::Button) -> Option<Self> {
+        match button {
+            iced::mouse::Button::Right => Some(Self::MouseRight),
+            iced::mouse::Button::Middle => Some(Self::MouseMiddle),
+            iced::mouse::Button::Back => Some(Self::MouseBack),
+            iced::mouse::Button::Forward => Some(Self::MouseForward),
+            iced::mouse::Button::Left | iced::mouse::Button::Other(_) => None,
+        }
+    }
+
+    /// Direction the scroll wheel moved in, if it moved
+    pub fn from_scroll(delta: iced::mouse::ScrollDelta) -> Option<Self> {
+        let (x, y) = match delta {
+            iced::mouse::ScrollDelta::Lines { x, y }
+            | iced::mouse::ScrollDelta::Pixels { x, y } => (x, y),
+        };
+
+        // positive values move the content right and down, which is scrolling left and up
+        if y.abs() >= x.abs() {
+            if y > 0.0 {
+                Some(Self::ScrollUp)
+            } else if y < 0.0 {
+                Some(Self::ScrollDown)
+            } else {
+                None
+            }
+        } else if x > 0.0 {
+            Some(Self::ScrollLeft)
+        } else {
+            Some(Self::ScrollRight)
+        }
+    }
+}
+
+/// A sequence of keys which are pressed one after another. It is never empty
 #[derive(Debug, Hash, PartialEq, PartialOrd, Ord, Eq, Clone)]
-pub struct KeySequence(pub (IcedKey, Option<IcedKey>));
+pub struct KeySequence(pub Vec<Input>);
+
+impl std::fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in &self.0 {
+            f.write_str(&key_name(key))?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the key as it is written in the config, e.g. `g`, `<space>` or `<mouse-right>`
+pub fn key_name(key: &Input) -> String {
+    match key {
+        Input::Key(IcedKey::Character(ch)) => ch.to_string(),
+        Input::Key(IcedKey::Named(named)) => Named::from_iced(*named)
+            .map_or_else(|| format!("<{named:?}>"), |named| format!("<{named}>")),
+        Input::Key(IcedKey::Unidentified) => String::from("<unidentified>"),
+        Input::Mouse(mouse) => format!("<{mouse}>"),
+    }
+}
+
+/// Name of the key with the modifiers held while pressing it, e.g. `g` or `ctrl+<space>`
+pub fn key_press_name((key, mods): &KeyPress) -> String {
+    if mods.0.is_empty() {
+        key_name(key)
+    } else {
+        format!("{mods}+{}", key_name(key))
+    }
+}
 
 /// Modifier keys
 #[derive(Debug, Default, Clone, Hash, Eq, PartialEq)]
 pub struct KeyMods(pub iced::keyboard::Modifiers);
 
+impl std::fmt::Display for KeyMods {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = [
+            (Modifiers::CTRL, "ctrl"),
+            (Modifiers::ALT, "alt"),
+            (Modifiers::LOGO, "super"),
+        ]
+        .into_iter()
+        .filter(|(modifier, _)| self.0.contains(*modifier))
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+
+        f.write_str(&names.join("+"))
+    }
+}
+
 impl FromStr for KeyMods {
     type Err = String;
 
@@ -125,7 +418,7 @@ impl std::str::FromStr for KeySequence {
                     //
                     // that means
                     // the first one was 100% a key.
-                    keys.push(IcedKey::Character(SmolStr::new("<")));
+                    keys.push(Input::Key(IcedKey::Character(SmolStr::new("<"))));
                 } else {
                     maybe_parsing_named_key = true;
                 }
@@ -133,27 +426,23 @@ impl std::str::FromStr for KeySequence {
                 // SPECIAL-CASE: there is no next character, the strings ends with
                 // `<` so it will be a keybinding
                 if chars.peek().is_none() {
-                    keys.push(IcedKey::Character(SmolStr::new("<")));
+                    keys.push(Input::Key(IcedKey::Character(SmolStr::new("<"))));
                 }
             } else if maybe_parsing_named_key {
                 if ch == '>' {
                     if named_key_buf.is_empty() {
                         // SPECIAL-CASE: in this case the user types exactly `<>`
                         // Make sure that the first `<` is also not ignored
-                        keys.push(IcedKey::Character(SmolStr::new("<")));
-                        keys.push(IcedKey::Character(SmolStr::new(">")));
+                        keys.push(Input::Key(IcedKey::Character(SmolStr::new("<"))));
+                        keys.push(Input::Key(IcedKey::Character(SmolStr::new(">"))));
                     } else {
                         // we are currently at the end of a named key
                         //
                         // <space>
                         //       x <-- we are here
                         //
-                        // it must be a valid key
-                        keys.push(IcedKey::Named(
-                            Named::from_str(&named_key_buf)
-                                .map_err(|err| format!("Invalid key: <{named_key_buf}>. {err}"))?
-                                .to_iced(),
-                        ));
+                        // it must be a valid key, or a mouse input
+                        keys.push(Input::from_name(&named_key_buf)?);
                         named_key_buf.clear();
                     }
                     maybe_parsing_named_key = false;
@@ -165,25 +454,13 @@ impl std::str::FromStr for KeySequence {
                     named_key_buf.push(ch);
                 }
             } else {
-                keys.push(IcedKey::Character(SmolStr::new(ch.to_string())));
+                keys.push(Input::Key(IcedKey::Character(SmolStr::new(ch.to_string()))));
             }
         }
-        let mut keys = keys.into_iter();
-        let Some(first_key) = keys.next() else {
+        if keys.is_empty() {
             return Err(String::from("Expected at least 1 key."));
-        };
-        let second_key = keys.next();
-        if keys.next().is_some() {
-            // because we only store a single previous key, we can't handle keybindings
-            // with more than 1 key. Since this is a screenshot app and not something like a
-            // text editor, I don't believe there is much utility in allowing 3 keys in a row or more.
-            //
-            // This greatly simplifies the code, as we don't have to be generic.
-            return Err(String::from(
-                "At the moment, only up to 2 keys in a sequence are supported.",
-            ));
         }
-        Ok(Self((first_key, second_key)))
+        Ok(Self(keys))
     }
 }
 
@@ -206,15 +483,11 @@ mod test {
                 )*
             }};
             (@seq Err, $message:literal ) => { Err($message.to_string()) };
-            (@seq $first:expr) => { Ok(KeySequence((assert_parsed_key_sequences!(@key $first), None))) };
-            (@seq $first:tt, $second:tt) => {{
-                Ok(KeySequence((
-                    assert_parsed_key_sequences!(@key $first),
-                    Some(assert_parsed_key_sequences!(@key $second))
-                )))
-            }};
-            (@key $key:ident) => { IcedKey::Named(key::Named::$key) };
-            (@key $key:literal) => { IcedKey::Character(SmolStr::new($key)) };
+            (@seq $($key:tt),+) => {
+                Ok(KeySequence(vec![$(assert_parsed_key_sequences!(@key $key)),+]))
+            };
+            (@key $key:ident) => { Input::Key(IcedKey::Named(key::Named::$key)) };
+            (@key $key:literal) => { Input::Key(IcedKey::Character(SmolStr::new($key))) };
         }
 
         assert_parsed_key_sequences! {
@@ -233,9 +506,358 @@ mod test {
             "<<space>" -> "<", Space
             "<f32><f31>" -> F32, F31
             "><f32>" -> ">", F32
-            "abc" -> Err, "At the moment, only up to 2 keys in a sequence are supported."
-            "<f32>b<f16>" -> Err, "At the moment, only up to 2 keys in a sequence are supported."
+            "abc" -> "a", "b", "c"
+            "<f32>b<f16>" -> F32, "b", F16
+            "<space>sf" -> Space, "s", "f"
             "<@>" -> Err, "Invalid key: <@>. Matching variant not found"
+            "<mouse-left>" -> Err, "The left mouse button can't be bound, as it is used to select"
+        }
+
+        assert_eq!(
+            "g<mouse-right><scroll-up>".parse::<KeySequence>(),
+            Ok(KeySequence(vec![
+                Input::Key(IcedKey::Character(SmolStr::new("g"))),
+                Input::Mouse(MouseInput::MouseRight),
+                Input::Mouse(MouseInput::ScrollUp),
+            ])),
+            "mouse inputs"
+        );
+    }
+
+    #[test]
+    fn ambiguous_prefixes() {
+        let mut key_map = KeyMap::default();
+        for (keys, action) in [
+            ("g", KeyAction::Exit),
+            ("gg", KeyAction::SetWidth),
+            ("gcc", KeyAction::SetHeight),
+            ("j", KeyAction::ClearSelection),
+        ] {
+            key_map.insert(
+                (
+                    keys.parse::<KeySequence>().expect("valid keys"),
+                    KeyMods::default(),
+                ),
+                action,
+            );
+        }
+
+        // press the keys one after another, optionally wait for the timeout,
+        // then return the triggered actions and the keys which are still pending
+        let press = |keys: &str, mods: Modifiers, timeout: bool| {
+            let mut pending = Vec::new();
+            let mut actions = Vec::new();
+            for key in keys.parse::<KeySequence>().expect("valid keys").0 {
+                actions.extend(key_map.press(&mut pending, (key, KeyMods(mods))));
+            }
+            if timeout {
+                actions.extend(key_map.timeout(&mut pending));
+            }
+            (
+                actions
+                    .iter()
+                    .map(|action| format!("{action:?}"))
+                    .collect::<Vec<_>>(),
+                pending.iter().map(key_press_name).collect::<String>(),
+            )
+        };
+        let none = Modifiers::empty();
+
+        assert_eq!(
+            press("j", none, false),
+            (vec![String::from("ClearSelection")], String::new()),
+            "single key"
+        );
+        assert_eq!(
+            press("gg", none, false),
+            (vec![String::from("SetWidth")], String::new()),
+            "longer sequence of an ambiguous prefix"
+        );
+        assert_eq!(
+            press("gcc", none, false),
+            (vec![String::from("SetHeight")], String::new()),
+            "3 keys"
+        );
+        assert_eq!(
+            press("g", none, false),
+            (vec![], String::from("g")),
+            "waits for the next key after an ambiguous prefix"
+        );
+        assert_eq!(
+            press("gc", none, false),
+            (vec![], String::from("gc")),
+            "waits for the next key of a sequence"
+        );
+        assert_eq!(
+            press("g", none, true),
+            (vec![String::from("Exit")], String::new()),
+            "ambiguous prefix is triggered after the timeout"
+        );
+        assert_eq!(
+            press("gc", none, true),
+            (vec![], String::new()),
+            "incomplete sequence does nothing after the timeout"
+        );
+        assert_eq!(
+            press("gj", none, false),
+            (
+                vec![String::from("Exit"), String::from("ClearSelection")],
+                String::new()
+            ),
+            "ambiguous prefix is triggered when the next key does not continue it"
+        );
+        assert_eq!(
+            press("gcj", none, false),
+            (vec![String::from("ClearSelection")], String::new()),
+            "incomplete sequence is dropped when the next key does not continue it"
+        );
+        assert_eq!(
+            press("ggg", none, true),
+            (
+                vec![String::from("SetWidth"), String::from("Exit")],
+                String::new()
+            ),
+            "keys after a triggered sequence start a new one"
+        );
+        assert_eq!(
+            press("g", Modifiers::CTRL, false),
+            (vec![], String::new()),
+            "modifiers must match"
+        );
+
+        let g = (
+            Input::Key(IcedKey::Character(SmolStr::new("g"))),
+            KeyMods::default(),
+        );
+        let c = (
+            Input::Key(IcedKey::Character(SmolStr::new("c"))),
+            KeyMods::default(),
+        );
+        let j = (
+            Input::Key(IcedKey::Character(SmolStr::new("j"))),
+            KeyMods::default(),
+        );
+        assert!(
+            key_map.continues(&[g.clone()], &c),
+            "`c` continues `g` towards `gcc`"
+        );
+        assert!(!key_map.continues(&[g.clone()], &j), "`j` interrupts `g`");
+        assert!(
+            key_map.continues(&[], &j),
+            "any bound key starts a sequence"
+        );
+    }
+
+    #[test]
+    fn continuations() {
+        let mut key_map = KeyMap::default();
+        for (keys, action) in [
+            ("g", KeyAction::Exit),
+            ("gg", KeyAction::SetWidth),
+            ("gcc", KeyAction::SetHeight),
+            ("gcd", KeyAction::SetHeight),
+            ("gx", KeyAction::ClearSelection),
+            ("j", KeyAction::ClearSelection),
+        ] {
+            key_map.insert(
+                (
+                    keys.parse::<KeySequence>().expect("valid keys"),
+                    KeyMods::default(),
+                ),
+                action,
+            );
         }
+
+        let continuations = |keys: &str| {
+            let pending = keys
+                .parse::<KeySequence>()
+                .map(|keys| {
+                    keys.0
+                        .into_iter()
+                        .map(|key| (key, KeyMods::default()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            key_map
+                .continuations(&pending)
+                .into_iter()
+                .map(|continuation| {
+                    (
+                        key_press_name(continuation.key),
+                        continuation.action.map(ToString::to_string),
+                        continuation.longer,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            continuations("g"),
+            [
+                (String::from("c"), None, 2),
+                (String::from("g"), Some(String::from("set-width")), 0),
+                (String::from("x"), Some(String::from("clear-selection")), 0),
+            ],
+            "sorted keys which continue the sequence"
+        );
+        assert_eq!(
+            continuations(""),
+            [
+                (String::from("g"), Some(String::from("exit")), 4),
+                (String::from("j"), Some(String::from("clear-selection")), 0),
+            ],
+            "keys which start a sequence"
+        );
+        assert_eq!(continuations("x"), [], "nothing starts with an unbound key");
+    }
+
+    /// Keybindings of the default config merged with the user config
+    fn merged_key_map(user_config: &str) -> KeyMap {
+        let default_config = knus::parse::<crate::config::options::DefaultKdlConfig>(
+            "<default-config>",
+            crate::config::DEFAULT_KDL_CONFIG_STR,
+        )
+        .expect("the default config is valid");
+        let user_config =
+            knus::parse::<crate::config::options::UserKdlConfig>("config.kdl", user_config)
+                .expect("valid config");
+
+        default_config
+            .merge_user_config(user_config)
+            .keys
+            .keys
+            .into_iter()
+            .map(|key| (*key).clone())
+            .collect()
+    }
+
+    #[test]
+    fn unbind() {
+        let bound = |key_map: &KeyMap, keys: &str, mods: Modifiers| {
+            key_map
+                .bindings()
+                .iter()
+                .any(|((sequence, key_mods), _)| sequence.to_string() == keys && key_mods.0 == mods)
+        };
+
+        let key_map = merged_key_map("");
+        assert!(bound(&key_map, "h", Modifiers::empty()), "bound by default");
+        assert!(
+            bound(&key_map, "<left>", Modifiers::CTRL),
+            "bound by default"
+        );
+
+        let key_map = merged_key_map(
+            "keys {\n  unbind key=h\n  unbind mod=ctrl key=<left>\n  unbind key=g\n}",
+        );
+        assert!(!bound(&key_map, "h", Modifiers::empty()), "unbound");
+        assert!(
+            !bound(&key_map, "<left>", Modifiers::CTRL),
+            "unbound with modifiers"
+        );
+        assert!(
+            bound(&key_map, "<left>", Modifiers::empty()),
+            "same key without modifiers is kept"
+        );
+        assert!(
+            bound(&key_map, "gh", Modifiers::empty()),
+            "longer sequences are kept"
+        );
+
+        let key_map = merged_key_map("keys {\n  unbind key=q\n  exit key=q\n}");
+        assert!(
+            bound(&key_map, "q", Modifiers::empty()),
+            "keys can be bound after being unbound"
+        );
+    }
+
+    #[test]
+    fn clear_defaults() {
+        assert_eq!(
+            merged_key_map(
+                "keys clear-defaults=#true {\n  exit key=q\n  goto top-left mod=ctrl key=gg\n  move left key=<space>h\n}"
+            )
+            .to_string(),
+            "keys {\n  move left key=\"<space>h\"\n  goto top-left mod=ctrl key=\"gg\"\n  exit key=\"q\"\n}\n",
+            "only the keybindings of the user, written like in the config"
+        );
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(
+            "g<space><left><<"
+                .parse::<KeySequence>()
+                .expect("valid keys")
+                .to_string(),
+            "g<space><left><<",
+            "written like in the config"
+        );
+        assert_eq!(
+            key_press_name(&(
+                Input::Key(IcedKey::Character(SmolStr::new("x"))),
+                KeyMods(Modifiers::CTRL | Modifiers::ALT)
+            )),
+            "ctrl+alt+x",
+            "modifiers come first"
+        );
+        assert_eq!(
+            [
+                KeyAction::Move(crate::rect::Direction::Left, 125),
+                KeyAction::Move(crate::rect::Direction::Left, u32::MAX),
+                KeyAction::Goto(crate::config::Place::TopLeft),
+                KeyAction::CopyToClipboard,
+            ]
+            .map(|action| action.to_string()),
+            [
+                "move left 125",
+                "move left",
+                "goto top-left",
+                "copy-to-clipboard"
+            ],
+            "actions are written like in the config, without default arguments"
+        );
+        assert_eq!(
+            KeyAction::CopyAsMarkdown.summary(),
+            "Copy a Markdown image of the selection. It links to the uploaded image if the selection was uploaded, otherwise the selection is saved into the `save-directory`",
+            "summary is the doc comment of the action, on one line"
+        );
+    }
+
+    #[test]
+    fn mouse_inputs() {
+        use iced::mouse::{Button, ScrollDelta};
+
+        assert_eq!(
+            [
+                ScrollDelta::Lines { x: 0.0, y: 1.0 },
+                ScrollDelta::Pixels { x: 0.5, y: -12.0 },
+                ScrollDelta::Lines { x: 2.0, y: 0.0 },
+                ScrollDelta::Pixels { x: -3.0, y: 1.0 },
+            ]
+            .map(MouseInput::from_scroll),
+            [
+                Some(MouseInput::ScrollUp),
+                Some(MouseInput::ScrollDown),
+                Some(MouseInput::ScrollLeft),
+                Some(MouseInput::ScrollRight),
+            ],
+            "the larger axis decides the direction"
+        );
+        assert_eq!(
+            MouseInput::from_scroll(ScrollDelta::Pixels { x: 0.0, y: 0.0 }),
+            None,
+            "no scroll"
+        );
+        assert_eq!(
+            [Button::Left, Button::Right, Button::Back].map(MouseInput::from_button),
+            [
+                None,
+                Some(MouseInput::MouseRight),
+                Some(MouseInput::MouseBack)
+            ],
+            "the left button is used to select"
+        );
     }
 }
@@ -29,7 +29,8 @@ pub struct Color {
 }
 
 /// A place on the rectangle
-#[derive(knus::DecodeScalar, Debug, Clone)]
+#[derive(knus::DecodeScalar, Debug, Clone, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
 pub enum Place {
     /// Center
     Center,
@@ -70,6 +71,7 @@ macro_rules! declare_config_options {
         /// The default config as read from the default config file, included as a static string in the binary.
         /// All values are required and must be specified
         #[derive(knus::Decode, Debug)]
+        #[knus(span_type = knus::span::Span)]
         pub struct DefaultKdlConfig {
             /// The default keybindings of ferrishot
             #[knus(child)]
@@ -77,6 +79,9 @@ macro_rules! declare_config_options {
             /// The default theme of ferrishot
             #[knus(child)]
             pub theme: DefaultKdlTheme,
+            /// Services which images can be uploaded to
+            #[knus(child)]
+            pub upload_providers: $crate::image_upload::UploadProviders,
             $(
                 $(#[$doc])*
                 #[knus(child, unwrap(argument))]
@@ -118,10 +123,23 @@ macro_rules! declare_config_options {
                 //
                 // Essentially what we want to make sure is that if the same key is defined twice,
                 // the user keybinding takes priority.
+                let user_keys = user_config.keys.unwrap_or_default();
+                if user_keys.clear_defaults {
+                    self.keys.keys.clear();
+                }
+                self.keys.keys.extend(user_keys.keys);
+
+                // providers with the same name as a default provider override it,
+                // as the last provider with a given name is used
+                let user_upload_providers = user_config.upload_providers.unwrap_or_default();
                 self
-                    .keys
-                    .keys
-                    .extend(user_config.keys.unwrap_or_default().keys);
+                    .upload_providers
+                    .providers
+                    .extend(user_upload_providers.providers);
+                self.upload_providers.sxcu.extend(user_upload_providers.sxcu);
+                if user_upload_providers.fallback.is_some() {
+                    self.upload_providers.fallback = user_upload_providers.fallback;
+                }
 
                 if let Some(user_theme) = user_config.theme {
                     self.theme = self.theme.merge_user_theme(user_theme);
@@ -134,6 +152,7 @@ macro_rules! declare_config_options {
         /// User's config. Everything is optional. Values will be merged with `DefaultKdlConfig`.
         /// And will take priority over the default values.
         #[derive(knus::Decode, Debug)]
+        #[knus(span_type = knus::span::Span)]
         pub struct UserKdlConfig {
             /// User-defined keybindings
             #[knus(child)]
@@ -141,6 +160,9 @@ macro_rules! declare_config_options {
             /// User-defined colors
             #[knus(child)]
             pub theme: Option<UserKdlTheme>,
+            /// User-defined upload providers
+            #[knus(child)]
+            pub upload_providers: Option<$crate::image_upload::UploadProviders>,
             $(
                 $(#[$doc])*
                 #[knus(child, unwrap(argument))]
@@ -154,6 +176,10 @@ macro_rules! declare_config_options {
             pub theme: Theme,
             /// Ferrishot's keybindings
             pub keys: $crate::config::key::KeyMap,
+            /// Services which images can be uploaded to
+            pub upload_providers: Vec<$crate::image_upload::UploadProvider>,
+            /// Names of the providers to try in order when the `default_image_upload_provider` fails
+            pub fallback_upload_providers: Vec<String>,
             $(
                 $(#[$doc])*
                 pub $key: $typ,
@@ -259,13 +285,22 @@ macro_rules! declare_theme_options {
 /// Which generates a structure like so, when parsed:
 ///
 /// ```no_compile
-/// Key::Keybind(10, false, 0.8, "hello", KeySequence("g", None), KeyMods::CTRL)
+/// Key::Keybind(10, false, 0.8, "hello", KeySequence(vec!["g"]), KeyMods::CTRL)
 /// ```
 #[macro_export]
 macro_rules! declare_key_options {
+    (@write_argument $f:ident, $field:ident) => {
+        write!($f, " {}", $field)?;
+    };
+    (@write_argument $f:ident, $field:ident, $default:expr) => {
+        // arguments with a default are only shown when they differ from it
+        if *$field != $default {
+            write!($f, " {}", $field)?;
+        }
+    };
     (
         $(
-            $(#[$key_attr:meta])*
+            $(#[doc = $doc:literal])*
             $KeyOption:ident $({$(
                 $(#[$arg_attr:meta])*
                 $field:ident: $Argument:ty $(= $default:expr)?,
@@ -278,7 +313,7 @@ macro_rules! declare_key_options {
         #[derive(knus::Decode, Debug, Clone)]
         pub enum Key {
             $(
-                $(#[$key_attr])*
+                $(#[doc = $doc])*
                 $KeyOption(
                     $($(
                         $(#[$arg_attr])*
@@ -312,12 +347,41 @@ macro_rules! declare_key_options {
         }
 
         /// The action associated with a key
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, strum::IntoStaticStr)]
+        #[strum(serialize_all = "kebab-case")]
         pub enum KeyAction {
             $(
-                $(#[$key_attr])*
+                $(#[doc = $doc])*
                 $KeyOption$(($($Argument,)*))?,
             )*
         }
+
+        impl KeyAction {
+            /// Lines of the documentation of this action
+            pub const fn doc(&self) -> &'static [&'static str] {
+                match self {
+                    $(
+                        Self::$KeyOption { .. } => &[$($doc),*],
+                    )*
+                }
+            }
+        }
+
+        /// The action as it is written in the config, e.g. `move left 1`
+        impl std::fmt::Display for KeyAction {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.into())?;
+                match self {
+                    $(
+                        Self::$KeyOption$(($($field),*))? => {
+                            $($(
+                                $crate::declare_key_options!(@write_argument f, $field $(, $default)?);
+                            )*)?
+                        }
+                    )*
+                }
+                Ok(())
+            }
+        }
     }
 }
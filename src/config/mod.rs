@@ -10,10 +10,11 @@
 //! extra processing for things that could not be trivially determined during deserialization.
 //!
 //! Such as:
-//! - Converting the list of keybindings into a structured `KeyMap` which can be indexed `O(1)` to
-//!   obtain the `Message` to execute for that action.
+//! - Converting the list of keybindings into a structured `KeyMap`, a trie which is walked
+//!   one key at a time to obtain the `Message` to execute for that action.
 //! - Adding opacity to colors
 
+mod check;
 mod cli;
 mod key;
 mod macros;
@@ -24,12 +25,14 @@ use crate::config::key::KeyMap;
 use crate::config::macros::Color;
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use std::sync::LazyLock;
 
 use options::{DefaultKdlConfig, UserKdlConfig};
 
-pub use cli::CLI;
+pub use check::check_config;
+pub use cli::{CLI, Command, Input, Output, Region, UploadsCommand};
+pub use key::{Continuation, Input, KeyMods, KeyPress, MouseInput, key_press_name};
 pub use macros::Place;
 pub use options::{Config, Key, KeyAction};
 
@@ -38,38 +41,89 @@ pub use options::{Config, Key, KeyAction};
 /// When modifying any of the config options, this will also need to be updated
 pub const DEFAULT_KDL_CONFIG_STR: &str = include_str!("../../default.kdl");
 
+/// Name of the default config in error messages
+const DEFAULT_CONFIG_NAME: &str = "<default-config>";
+
 /// Configuration of the app
 ///
 /// Static as it will never change once the app is launched.
 /// It also makes it easy to get the config values anywhere from the app, even where we don't have access to the `App`.
-pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
-    let kdl_config = (|| -> miette::Result<DefaultKdlConfig> {
-        let config_file = CLI.config_file.as_str();
-        let config_file_path = PathBuf::from(config_file);
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| match load(&CLI.config_file) {
+    Ok(config) => config,
+    Err(miette_error) => {
+        eprintln!("{miette_error:?}");
+        std::process::exit(1);
+    }
+});
 
-        let default_config =
-            knus::parse::<DefaultKdlConfig>("<default-config>", DEFAULT_KDL_CONFIG_STR)?;
+/// Read the config file, merged with the default config
+fn load(config_file: &str) -> miette::Result<Config> {
+    let (default_config, user_config) = parse(config_file)?;
+    build(default_config.merge_user_config(user_config), config_file)
+}
 
-        let user_config = knus::parse::<UserKdlConfig>(
-            &CLI.config_file,
-            // if there is no config file, act as if it's simply empty
-            &fs::read_to_string(&config_file_path).unwrap_or_default(),
-        )?;
+/// Parse the default config and the config file, without merging them
+fn parse(config_file: &str) -> miette::Result<(DefaultKdlConfig, UserKdlConfig)> {
+    let default_config =
+        knus::parse::<DefaultKdlConfig>(DEFAULT_CONFIG_NAME, DEFAULT_KDL_CONFIG_STR)?;
 
-        Ok(default_config.merge_user_config(user_config))
-    })();
+    let user_config = knus::parse::<UserKdlConfig>(
+        config_file,
+        // if there is no config file, act as if it's simply empty
+        &fs::read_to_string(config_file).unwrap_or_default(),
+    )?;
 
-    match kdl_config {
-        Ok(kdl_config) => Config {
-            instant: kdl_config.instant,
-            default_image_upload_provider: kdl_config.default_image_upload_provider,
-            size_indicator: kdl_config.size_indicator,
-            theme: kdl_config.theme.into(),
-            keys: kdl_config.keys.keys.into_iter().collect::<KeyMap>(),
-        },
-        Err(miette_error) => {
-            eprintln!("{miette_error:?}");
-            std::process::exit(1);
-        }
-    }
-});
+    Ok((default_config, user_config))
+}
+
+/// Process the merged config into a `Config`
+fn build(mut kdl_config: DefaultKdlConfig, config_file: &str) -> miette::Result<Config> {
+    let fallback_upload_providers = kdl_config
+        .upload_providers
+        .fallback
+        .take()
+        .map(|fallback| fallback.providers)
+        .unwrap_or_default();
+
+    // `.sxcu` files are relative to the config file
+    let upload_providers = std::mem::take(&mut kdl_config.upload_providers).resolve(
+        Path::new(config_file)
+            .parent()
+            .unwrap_or_else(|| Path::new(".")),
+    )?;
+
+    Ok(Config {
+        instant: kdl_config.instant,
+        default_image_upload_provider: kdl_config.default_image_upload_provider,
+        size_indicator: kdl_config.size_indicator,
+        delay: CLI.delay.unwrap_or(kdl_config.delay),
+        delay_notification: kdl_config.delay_notification,
+        format: CLI.format.unwrap_or(kdl_config.format),
+        jpeg_quality: kdl_config.jpeg_quality,
+        png_compression: kdl_config.png_compression,
+        webp_lossless: kdl_config.webp_lossless,
+        save_directory: kdl_config.save_directory,
+        filename_template: kdl_config.filename_template,
+        copy_file: kdl_config.copy_file,
+        copy_path_as_text: kdl_config.copy_path_as_text,
+        copy_image_with_link: kdl_config.copy_image_with_link,
+        history_size: kdl_config.history_size,
+        history_max_disk_usage: kdl_config.history_max_disk_usage,
+        key_timeout: kdl_config.key_timeout,
+        theme: kdl_config.theme.into(),
+        keys: kdl_config
+            .keys
+            .keys
+            .into_iter()
+            .map(|key| (*key).clone())
+            .collect::<KeyMap>(),
+        upload_providers,
+        fallback_upload_providers,
+        upload_timeout: kdl_config.upload_timeout,
+        upload_retries: kdl_config.upload_retries,
+        upload_retry_delay: kdl_config.upload_retry_delay,
+        http_proxy: kdl_config.http_proxy,
+        ca_certificates: kdl_config.ca_certificates,
+        user_agent: kdl_config.user_agent,
+    })
+}
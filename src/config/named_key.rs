@@ -6,7 +6,7 @@
 /// how certain variants serialize
 macro_rules! named_keys {
     ($($Key:ident $(= $renamed:literal)?),* $(,)?) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, strum::EnumString, strum::EnumIter)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, strum::EnumString, strum::EnumIter, strum::Display)]
         #[strum(serialize_all = "kebab-case")]
         #[expect(
             clippy::upper_case_acronyms,
@@ -26,6 +26,11 @@ macro_rules! named_keys {
                     $(Self::$Key => iced::keyboard::key::Named::$Key),*
                 }
             }
+
+            /// The key of Iced, if it can be written in the config
+            pub fn from_iced(named: iced::keyboard::key::Named) -> Option<Self> {
+                <Self as strum::IntoEnumIterator>::iter().find(|key| key.to_iced() == named)
+            }
         }
     };
 }
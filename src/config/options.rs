@@ -1,7 +1,7 @@
 //! Declare config options
 
 use crate::config::Place;
-use crate::image_upload::ImageUploadService;
+use crate::image_format::{EncodeOptions, ImageFormat, PngCompression};
 use crate::rect::Direction;
 
 crate::declare_config_options! {
@@ -12,17 +12,112 @@ crate::declare_config_options! {
     /// When this is `true`, while you are selecting the first square pressing the Right mouse button just once will
     /// cancel this effect and not instantly copy the screenshot.
     instant: bool,
-    /// The default image service to use when uploading images to the internet.
-    /// We have multiple options because some of them can be down / unreliable etc.
+    /// Name of the provider to use when uploading images to the internet.
+    /// Providers are declared in the `upload-providers` section.
     ///
-    /// You may also get rate limited by the service if you send too many images, so you can try a different
+    /// You may get rate limited by the service if you send too many images, so you can try a different
     /// one if that happens.
-    default_image_upload_provider: ImageUploadService,
+    default_image_upload_provider: String,
+    /// Give up on an upload, or any other HTTP request, which takes longer than this many seconds
+    upload_timeout: u32,
+    /// How many times to retry an upload which failed because of a timeout, a network error
+    /// or an error of the server, before trying the next provider in `upload-providers { fallback }`
+    upload_retries: u32,
+    /// How many milliseconds to wait before retrying an upload. Doubles after each retry
+    upload_retry_delay: u32,
+    /// URL of the proxy to send uploads through, e.g. `http://proxy.example.com:8080`.
+    ///
+    /// If empty, the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables are used
+    http_proxy: String,
+    /// Path to a PEM file with certificate authorities to trust in addition to the built-in ones,
+    /// e.g. the one of your company's proxy. A leading `~` is replaced by your home directory.
+    ///
+    /// Ignored if empty
+    ca_certificates: String,
+    /// Value of the `User-Agent` header sent with uploads. If empty, `ferrishot/<version>` is used
+    user_agent: String,
     /// Renders a size indicator in the bottom left corner.
     /// It shows the current height and width of the selection.
     ///
     /// You can manually enter a value to change the selection by hand.
     size_indicator: bool,
+    /// Wait this many seconds before taking the screenshot.
+    ///
+    /// Gives you time to open menus, tooltips or trigger hover states which
+    /// would otherwise disappear as soon as ferrishot launches.
+    delay: u32,
+    /// While waiting for the `delay` to run out, show a desktop notification
+    /// each second with how much time is left.
+    delay_notification: bool,
+    /// Format of saved screenshots, if it cannot be determined from the file extension.
+    ///
+    /// One of: `png`, `jpeg`, `webp`, `avif`, `qoi`, `bmp`
+    format: ImageFormat,
+    /// Quality of JPEG images, from 1 (worst) to 100 (best)
+    jpeg_quality: u8,
+    /// How much to compress PNG images. Better compression is slower.
+    ///
+    /// One of: `fast`, `default`, `best`
+    png_compression: PngCompression,
+    /// Save WebP images without any loss in quality.
+    ///
+    /// Lossy WebP is currently not supported, so this must be `#true` to use WebP
+    webp_lossless: bool,
+    /// Directory where `save-to-directory` saves screenshots, without asking where to save them.
+    ///
+    /// A leading `~` is replaced by your home directory. The directory is created if it does not exist.
+    save_directory: String,
+    /// Name of the files saved by `save-to-directory`. Placeholders:
+    ///
+    /// - `{date}`: when the screenshot was saved. Pass a custom format with `{date:%Y-%m-%d_%H-%M-%S}`
+    /// - `{width}`, `{height}`: size of the screenshot
+    /// - `{monitor}`: name of the monitor the selection is on
+    /// - `{n}`: smallest number such that no existing file is overwritten
+    ///
+    /// If a file with the same name exists, `-1`, `-2`... is appended to the name.
+    /// If the name does not end with the extension of an image format, the one of `format` is appended.
+    filename_template: String,
+    /// When copying a screenshot, also save it into the `save-directory` and offer the file
+    /// as `text/uri-list`, so that file managers paste the file, and chat apps which take
+    /// attachments get a file with a name.
+    ///
+    /// Only on Linux, where the clipboard can hold several formats at once
+    copy_file: bool,
+    /// With `copy-file`, also offer the path of the saved file as `text/plain`,
+    /// so that pasting into a terminal or a text editor gives the path
+    copy_path_as_text: bool,
+    /// When copying the link of an uploaded image, also offer the image itself as `image/png`.
+    ///
+    /// Applications which accept images, like chat apps, will then paste the image instead of the link.
+    /// Only on Linux, where the clipboard can hold several formats at once
+    copy_image_with_link: bool,
+    /// How many of the most recent captures to keep in the history, which is opened
+    /// with `ferrishot --history`. Captures are added when they are copied, saved or uploaded.
+    ///
+    /// They are kept as PNG files in the `ferrishot/captures` directory inside of the data
+    /// directory: `~/.local/share` on Linux and macOS, and `%APPDATA%` on Windows.
+    ///
+    /// `0` disables the history, which is the default so that no screenshots are left behind
+    history_size: u32,
+    /// Largest amount of disk space, in MiB, that the history may take up.
+    /// The oldest captures are removed once it takes up more
+    history_max_disk_usage: u32,
+    /// How many milliseconds to wait for the next key of a key sequence.
+    ///
+    /// When a key is bound on its own and as the start of a longer sequence, e.g. `g` and `gg`,
+    /// the single key is triggered once this much time passed without pressing another key
+    key_timeout: u32,
+}
+
+impl Config {
+    /// Options used when encoding images
+    pub const fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            jpeg_quality: self.jpeg_quality,
+            png_compression: self.png_compression,
+            webp_lossless: self.webp_lossless,
+        }
+    }
 }
 
 crate::declare_key_options! {
@@ -30,10 +125,23 @@ crate::declare_key_options! {
     PickTopLeftCorner,
     /// Open a grid of letters to pick the bottom right corner in 3 keystrokes
     PickBottomRightCorner,
+    /// Move the corner of the selection which is nearest to the cursor to the cursor.
+    /// When bound to a mouse button, the corner follows the cursor while the button is held
+    SnapToCursor,
     /// Copy the selected region as a screenshot to the clipboard
     CopyToClipboard,
     /// Save the screenshot as a path
     SaveScreenshot,
+    /// Save the screenshot into the `save-directory`, without opening a file dialog
+    SaveToDirectory,
+    /// Copy the selection as a `data:image/png;base64,...` URI, which contains the whole image
+    CopyAsDataUri,
+    /// Copy a Markdown image of the selection. It links to the uploaded image if the selection
+    /// was uploaded, otherwise the selection is saved into the `save-directory`
+    CopyAsMarkdown,
+    /// Copy an HTML `<img>` tag of the selection. Like `copy-as-markdown`, it links to the uploaded
+    /// image, or to the selection saved into the `save-directory`
+    CopyAsHtml,
     /// Set the width to whatever number is currently pressed
     SetWidth,
     /// Set the height to whatever number is currently pressed
@@ -62,6 +170,21 @@ crate::declare_key_options! {
     /// Move rectangle to a place
     Goto {
         place: Place,
+    },
+    /// Remove the keybinding of the keys, e.g. to free keys which are bound in the default config.
+    /// Longer key sequences which start with the keys are kept
+    Unbind
+}
+
+impl KeyAction {
+    /// First paragraph of the documentation of the action, on a single line
+    pub fn summary(&self) -> String {
+        self.doc()
+            .iter()
+            .map(|line| line.trim())
+            .take_while(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 }
 
@@ -98,4 +221,10 @@ crate::declare_theme_options! {
     icon_bg,
     /// Color of icons around the selection
     icon_fg,
+    /// Background color of the popup which lists the keys that continue a key sequence
+    which_key_bg,
+    /// Color of the descriptions in the popup which lists the keys that continue a key sequence
+    which_key_fg,
+    /// Color of the keys in the popup which lists the keys that continue a key sequence
+    which_key_key,
 }
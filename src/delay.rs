@@ -0,0 +1,142 @@
+//! Wait for some time before taking the screenshot
+//!
+//! This gives the user time to open menus, tooltips and similar
+//! which would disappear as soon as ferrishot is launched.
+
+use std::time::Duration;
+
+/// Time to wait after the countdown, so that notification daemons which fade
+/// notifications out instead of closing them right away are done with it
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Wait for `seconds` before returning.
+///
+/// Calls `on_tick` with the amount of seconds left, once at the start of every second.
+/// Whatever it returns, such as a notification, is passed to `on_tick_end` at the end of
+/// that second. After the last second, the countdown waits for a bit longer, so that
+/// nothing shown by `on_tick` is still visible when the countdown returns.
+///
+/// `sleep` is responsible for the actual waiting. All of them are passed in so that the
+/// countdown can be tested without actually waiting.
+pub fn countdown<T>(
+    seconds: u32,
+    mut sleep: impl FnMut(Duration),
+    mut on_tick: impl FnMut(u32) -> T,
+    mut on_tick_end: impl FnMut(T),
+) {
+    for seconds_left in (1..=seconds).rev() {
+        let tick = on_tick(seconds_left);
+        sleep(Duration::from_secs(1));
+        on_tick_end(tick);
+    }
+
+    if seconds > 0 {
+        sleep(SETTLE_DELAY);
+    }
+}
+
+/// Whether notifications can be closed before their timeout runs out
+const CAN_CLOSE_NOTIFICATIONS: bool = cfg!(all(unix, not(target_os = "macos")));
+
+/// Close the notification, so that it does not end up in the screenshot
+#[cfg(all(unix, not(target_os = "macos")))]
+fn close(notification: notify_rust::NotificationHandle) {
+    notification.close();
+}
+
+/// Notifications can't be closed on this platform, they disappear after their timeout
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn close<T>(_: T) {}
+
+/// Wait for the given amount of seconds, optionally showing a desktop
+/// notification with the countdown
+pub fn wait(seconds: u32, show_notification: bool) {
+    countdown(
+        seconds,
+        std::thread::sleep,
+        |seconds_left| {
+            log::info!("Taking screenshot in {seconds_left}s");
+
+            // a notification which can't be closed could still be visible
+            // when the screenshot is taken
+            if !show_notification || (seconds_left == 1 && !CAN_CLOSE_NOTIFICATIONS) {
+                return None;
+            }
+
+            // this is just a decoration, so it's ok if we fail to show the notification
+            notify_rust::Notification::new()
+                .summary(&format!("Taking screenshot in {seconds_left}..."))
+                .timeout(notify_rust::Timeout::Milliseconds(1000))
+                .show()
+                .ok()
+        },
+        |notification| {
+            if let Some(notification) = notification {
+                close(notification);
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Something that happened during the countdown
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Event {
+        /// Slept for the duration
+        Sleep(Duration),
+        /// A tick with this many seconds left started
+        Tick(u32),
+        /// The tick with this many seconds left ended
+        TickEnd(u32),
+    }
+
+    /// What happens during a countdown of `seconds`, in order
+    fn events(seconds: u32) -> Vec<Event> {
+        let events = RefCell::new(Vec::new());
+
+        countdown(
+            seconds,
+            |duration| events.borrow_mut().push(Event::Sleep(duration)),
+            |left| {
+                events.borrow_mut().push(Event::Tick(left));
+                left
+            },
+            |left| events.borrow_mut().push(Event::TickEnd(left)),
+        );
+
+        events.into_inner()
+    }
+
+    #[test]
+    fn countdown_ticks_every_second() {
+        let second = Event::Sleep(Duration::from_secs(1));
+
+        assert_eq!(
+            events(2),
+            [
+                Event::Tick(2),
+                second,
+                Event::TickEnd(2),
+                Event::Tick(1),
+                second,
+                Event::TickEnd(1),
+                Event::Sleep(SETTLE_DELAY),
+            ],
+            "each tick lasts 1 second and ends before the next one. The last one ends \
+             before waiting for whatever it showed to disappear"
+        );
+    }
+
+    #[test]
+    fn no_delay_does_not_wait() {
+        assert!(
+            events(0).is_empty(),
+            "there should be no ticks or sleeping without a delay"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Capture a region of the screen without opening the app
+//!
+//! This is useful for scripts, where a fixed area of the screen must be captured
+//! without any user interaction.
+//!
+//! Also contains reading and writing of images from files and standard input/output.
+
+use std::io::{Read as _, Write as _};
+
+use iced::{Point, Size};
+
+use crate::CONFIG;
+use crate::config::{Input, Output, Region};
+use crate::image_format;
+use crate::screenshot::Screenshot;
+use crate::widget::selection::Selection;
+
+/// Could not capture the region
+#[derive(thiserror::Error, Debug)]
+pub enum RegionError {
+    /// The monitor passed by `--monitor` does not exist
+    #[error("There is no monitor named `{0}`")]
+    UnknownMonitor(String),
+    /// The region does not fit inside of the screenshot
+    #[error(
+        "The region {width}x{height} at ({x}, {y}) does not fit inside of the captured area of {area_width}x{area_height}"
+    )]
+    OutOfBounds {
+        /// x-coordinate of the region
+        x: u32,
+        /// y-coordinate of the region
+        y: u32,
+        /// Width of the region
+        width: u32,
+        /// Height of the region
+        height: u32,
+        /// Width of the area which we are capturing from
+        area_width: u32,
+        /// Height of the area which we are capturing from
+        area_height: u32,
+    },
+}
+
+/// Crop the region out of the screenshot
+///
+/// If `monitor` is specified, the region is relative to the top-left corner of that
+/// monitor, and must fit inside of it. Monitors can be specified by their name, or by their
+/// index.
+pub fn crop_region(
+    screenshot: &Screenshot,
+    region: Region,
+    monitor: Option<&str>,
+) -> Result<image::DynamicImage, RegionError> {
+    let (offset_x, offset_y, area_width, area_height) = match monitor {
+        Some(monitor) => {
+            let monitor = screenshot
+                .monitors()
+                .iter()
+                .find(|m| m.name == monitor)
+                .or_else(|| {
+                    monitor
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| screenshot.monitors().get(index))
+                })
+                .ok_or_else(|| RegionError::UnknownMonitor(monitor.to_string()))?;
+
+            (monitor.x, monitor.y, monitor.width, monitor.height)
+        }
+        None => (0, 0, screenshot.width(), screenshot.height()),
+    };
+
+    let fits = region
+        .x
+        .checked_add(region.width)
+        .is_some_and(|right| right <= area_width)
+        && region
+            .y
+            .checked_add(region.height)
+            .is_some_and(|bottom| bottom <= area_height);
+
+    if !fits {
+        return Err(RegionError::OutOfBounds {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+            area_width,
+            area_height,
+        });
+    }
+
+    let selection = Selection::new(Point::new(
+        (offset_x + region.x) as f32,
+        (offset_y + region.y) as f32,
+    ))
+    .with_size(|_| Size::new(region.width as f32, region.height as f32));
+
+    Ok(selection.process_image(screenshot.width(), screenshot.height(), screenshot.bytes()))
+}
+
+/// Read an image from the input, to be used instead of a screenshot
+pub fn read_image(input: &Input) -> miette::Result<Screenshot> {
+    let image = match input {
+        Input::File(path) => image::open(path)
+            .map_err(|err| miette::miette!("Failed to open {}: {err}", path.display()))?,
+        Input::Stdin => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .lock()
+                .read_to_end(&mut bytes)
+                .map_err(|err| miette::miette!("Failed to read standard input: {err}"))?;
+            image::load_from_memory(&bytes).map_err(|err| {
+                miette::miette!("Failed to decode the image from standard input: {err}")
+            })?
+        }
+    };
+
+    Ok(Screenshot::from_image(image.into_rgba8()))
+}
+
+/// Format in which the image is written to the output.
+///
+/// The format is determined from the file extension, falling back to the configured `format`.
+/// When writing to standard output, the configured `format` is used.
+pub fn output_format(
+    output: &Output,
+) -> Result<image_format::ImageFormat, image_format::SaveError> {
+    match output {
+        Output::File(path) => {
+            image_format::ImageFormat::from_path(path).map(|format| format.unwrap_or(CONFIG.format))
+        }
+        Output::Stdout => Ok(CONFIG.format),
+    }
+}
+
+/// Write the image to the output.
+///
+/// The format is determined from the file extension, falling back to the configured `format`.
+/// When writing to standard output, the configured `format` is used.
+///
+/// # Returns
+///
+/// The path where the image was saved, or `None` if it was written to standard output
+pub fn write_image(
+    image: &image::DynamicImage,
+    output: &Output,
+) -> miette::Result<Option<std::path::PathBuf>> {
+    let options = CONFIG.encode_options();
+
+    match output {
+        Output::File(path) => image_format::save(image, path, CONFIG.format, &options)
+            .map(Some)
+            .map_err(|err| miette::miette!("Failed to save the screenshot: {err}")),
+        Output::Stdout => {
+            let bytes = CONFIG
+                .format
+                .encode(image, &options)
+                .map_err(|err| miette::miette!("Failed to encode the screenshot: {err}"))?;
+
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(&bytes)
+                .and_then(|()| stdout.flush())
+                .map(|()| None)
+                .map_err(|err| miette::miette!("Failed to write the screenshot: {err}"))
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! The HTTP client used to upload images, built from the `http-proxy`, `ca-certificates`,
+//! `user-agent` and `upload-timeout` config options
+
+use std::{
+    error::Error as _,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use crate::CONFIG;
+
+/// A single client for HTTP requests
+///
+/// It is an error if the config contains invalid HTTP settings. The error is only shown
+/// once something is sent, so that it does not prevent taking screenshots
+pub static CLIENT: LazyLock<Result<reqwest::Client, ClientError>> = LazyLock::new(|| {
+    build_client(&ClientSettings {
+        proxy: &CONFIG.http_proxy,
+        ca_certificates: &CONFIG.ca_certificates,
+        user_agent: &CONFIG.user_agent,
+        timeout: Duration::from_secs(CONFIG.upload_timeout.into()),
+    })
+});
+
+/// The HTTP client, or why it could not be created
+pub fn client() -> Result<&'static reqwest::Client, ClientError> {
+    CLIENT.as_ref().map_err(Clone::clone)
+}
+
+/// Could not create the HTTP client
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum ClientError {
+    /// The `http-proxy` is invalid
+    #[error("Invalid `http-proxy` {url}: {}", describe_error(err))]
+    InvalidProxy {
+        /// The proxy
+        url: String,
+        /// Why it is invalid
+        err: Arc<reqwest::Error>,
+    },
+    /// Could not expand `~` in `ca-certificates`
+    #[error("Could not determine the home directory: {0}")]
+    HomeDir(Arc<etcetera::HomeDirError>),
+    /// Could not read the file with the `ca-certificates`
+    #[error("Could not read `ca-certificates` from {}: {err}", path.display())]
+    ReadCertificates {
+        /// Path to the file
+        path: PathBuf,
+        /// Why it could not be read
+        err: Arc<std::io::Error>,
+    },
+    /// The file with the `ca-certificates` does not contain valid certificates
+    #[error("Invalid `ca-certificates` in {}: {reason}", path.display())]
+    InvalidCertificates {
+        /// Path to the file
+        path: PathBuf,
+        /// Why they are invalid
+        reason: String,
+    },
+    /// The `user-agent` can't be sent in a header
+    #[error("Invalid `user-agent` {0:?}")]
+    InvalidUserAgent(String),
+    /// Could not create the client, e.g. because the TLS backend failed to initialize
+    #[error("Could not create the HTTP client: {}", describe_error(.0))]
+    Build(Arc<reqwest::Error>),
+}
+
+/// Settings of the HTTP client
+#[derive(Debug, Clone, Copy)]
+pub struct ClientSettings<'a> {
+    /// URL of the proxy which all requests go through. If empty, the proxy is taken from the
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables
+    pub proxy: &'a str,
+    /// Path to a PEM file with certificate authorities to trust, in addition to the
+    /// built-in ones. Ignored if empty
+    pub ca_certificates: &'a str,
+    /// Value of the `User-Agent` header. If empty, `ferrishot/<version>` is used
+    pub user_agent: &'a str,
+    /// Give up on requests which take longer than this
+    pub timeout: Duration,
+}
+
+/// Create the HTTP client with these settings
+pub fn build_client(settings: &ClientSettings) -> Result<reqwest::Client, ClientError> {
+    let user_agent = if settings.user_agent.is_empty() {
+        format!("ferrishot/{}", env!("CARGO_PKG_VERSION"))
+    } else {
+        settings.user_agent.to_string()
+    };
+
+    if reqwest::header::HeaderValue::from_str(&user_agent).is_err() {
+        return Err(ClientError::InvalidUserAgent(user_agent));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .timeout(settings.timeout);
+
+    if !settings.proxy.is_empty() {
+        builder = builder.proxy(reqwest::Proxy::all(settings.proxy).map_err(|err| {
+            ClientError::InvalidProxy {
+                url: settings.proxy.to_string(),
+                err: Arc::new(err),
+            }
+        })?);
+    }
+
+    if !settings.ca_certificates.is_empty() {
+        let path = crate::util::expand_home(settings.ca_certificates)
+            .map_err(|err| ClientError::HomeDir(Arc::new(err)))?;
+        for certificate in read_certificates(&path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|err| ClientError::Build(Arc::new(err)))
+}
+
+/// Read every certificate in the PEM file
+fn read_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, ClientError> {
+    /// Marks the start of each certificate in the file
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+
+    let invalid = |reason: String| ClientError::InvalidCertificates {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let pem = std::fs::read_to_string(path).map_err(|err| ClientError::ReadCertificates {
+        path: path.to_path_buf(),
+        err: Arc::new(err),
+    })?;
+
+    let certificates = pem
+        .match_indices(BEGIN)
+        .map(|(start, _)| {
+            let end = pem[start + BEGIN.len()..]
+                .find(BEGIN)
+                .map_or(pem.len(), |end| start + BEGIN.len() + end);
+
+            reqwest::Certificate::from_pem(pem[start..end].as_bytes())
+                .map_err(|err| invalid(describe_error(&err)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if certificates.is_empty() {
+        return Err(invalid(format!("the file does not contain `{BEGIN}`")));
+    }
+
+    Ok(certificates)
+}
+
+/// Describe the error, including the underlying errors, with a hint about how to fix it
+/// if it's caused by the network setup.
+///
+/// The `Display` of a `reqwest::Error` only says e.g. "error sending request", and leaves
+/// out the actual reason, like an untrusted certificate
+pub fn describe_error(err: &reqwest::Error) -> String {
+    let mut description = err.to_string();
+    let mut source = err.source();
+
+    while let Some(err) = source {
+        let message = err.to_string();
+        // some errors repeat the message of their source
+        if !description.ends_with(&message) {
+            description.push_str(": ");
+            description.push_str(&message);
+        }
+        source = err.source();
+    }
+
+    let lowercase = description.to_lowercase();
+    let hint = if err.is_timeout() {
+        Some("Increase the `upload-timeout` if the connection is slow")
+    } else if lowercase.contains("certificate") {
+        Some(
+            "If you are behind a proxy with its own certificate authority, add it to `ca-certificates`",
+        )
+    } else if lowercase.contains("proxy") || err.is_connect() {
+        Some(
+            "Check your connection. If you are behind a proxy, set the `http-proxy` option, or the `HTTP_PROXY` and `HTTPS_PROXY` variables",
+        )
+    } else {
+        None
+    };
+
+    match hint {
+        Some(hint) => format!("{description}. {hint}"),
+        None => description,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::image_upload::mock_server::MockServer;
+
+    /// Settings which don't change anything
+    const SETTINGS: ClientSettings = ClientSettings {
+        proxy: "",
+        ca_certificates: "",
+        user_agent: "",
+        timeout: Duration::from_secs(10),
+    };
+
+    #[tokio::test]
+    async fn user_agent() {
+        let server = MockServer::start(200, "");
+
+        build_client(&SETTINGS)
+            .expect("valid settings")
+            .get(server.url("/"))
+            .send()
+            .await
+            .expect("request succeeds");
+        assert_eq!(
+            server.request().header("user-agent"),
+            Some(concat!("ferrishot/", env!("CARGO_PKG_VERSION"))),
+            "default user agent"
+        );
+
+        let server = MockServer::start(200, "");
+        build_client(&ClientSettings {
+            user_agent: "corporate-screenshots/1.0",
+            ..SETTINGS
+        })
+        .expect("valid settings")
+        .get(server.url("/"))
+        .send()
+        .await
+        .expect("request succeeds");
+        assert_eq!(
+            server.request().header("user-agent"),
+            Some("corporate-screenshots/1.0"),
+            "custom user agent"
+        );
+    }
+
+    #[tokio::test]
+    async fn proxy() {
+        let proxy = MockServer::start(200, "");
+        let proxy_url = proxy.url("");
+
+        build_client(&ClientSettings {
+            proxy: &proxy_url,
+            ..SETTINGS
+        })
+        .expect("valid settings")
+        .get("http://images.example.invalid/upload")
+        .send()
+        .await
+        .expect("request succeeds");
+
+        assert_eq!(
+            proxy.request().path,
+            "http://images.example.invalid/upload",
+            "the request was sent to the proxy"
+        );
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert!(
+            matches!(
+                build_client(&ClientSettings {
+                    proxy: "not a url",
+                    ..SETTINGS
+                }),
+                Err(ClientError::InvalidProxy { .. })
+            ),
+            "invalid proxy"
+        );
+
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let path = dir.path().join("ca.pem");
+        let path_str = path.to_string_lossy().into_owned();
+
+        assert!(
+            matches!(
+                build_client(&ClientSettings {
+                    ca_certificates: &path_str,
+                    ..SETTINGS
+                }),
+                Err(ClientError::ReadCertificates { .. })
+            ),
+            "missing certificates"
+        );
+
+        std::fs::write(&path, "not a certificate").expect("write the certificates");
+        assert!(
+            matches!(
+                build_client(&ClientSettings {
+                    ca_certificates: &path_str,
+                    ..SETTINGS
+                }),
+                Err(ClientError::InvalidCertificates { .. })
+            ),
+            "no certificates in the file"
+        );
+
+        assert!(
+            matches!(
+                build_client(&ClientSettings {
+                    user_agent: "new\nline",
+                    ..SETTINGS
+                }),
+                Err(ClientError::InvalidUserAgent(_))
+            ),
+            "invalid user agent"
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_connection_error() {
+        // nothing is listening on the port once the listener is dropped
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("bind to a random port")
+            .port();
+
+        let err = build_client(&SETTINGS)
+            .expect("valid settings")
+            .get(format!("http://127.0.0.1:{port}"))
+            .send()
+            .await
+            .expect_err("nothing is listening");
+        let description = describe_error(&err);
+
+        assert!(
+            description.len() > err.to_string().len(),
+            "the underlying reason is included: {description}"
+        );
+        assert!(
+            description.contains("`http-proxy`"),
+            "hint about the proxy: {description}"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Encode the screenshot in one of the supported image formats
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use image::{
+    DynamicImage,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+};
+use strum::IntoEnumIterator as _;
+
+/// Format of the saved image
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    knus::DecodeScalar,
+    clap::ValueEnum,
+    strum::EnumIter,
+)]
+pub enum ImageFormat {
+    /// Lossless, supported everywhere
+    #[default]
+    Png,
+    /// Lossy, without transparency. Quality is controlled by `jpeg-quality`
+    Jpeg,
+    /// Lossless WebP
+    Webp,
+    /// AV1 Image File Format
+    Avif,
+    /// The "Quite OK Image Format"
+    Qoi,
+    /// Windows Bitmap
+    Bmp,
+}
+
+/// Compression level of PNG images
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, knus::DecodeScalar)]
+pub enum PngCompression {
+    /// Fast, but the file is larger
+    Fast,
+    /// A balance between speed and size
+    #[default]
+    Default,
+    /// Slow, but the file is smaller
+    Best,
+}
+
+/// Options which affect how images are encoded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Quality of JPEG images, from 1 to 100
+    pub jpeg_quality: u8,
+    /// Compression level of PNG images
+    pub png_compression: PngCompression,
+    /// Encode WebP images losslessly
+    pub webp_lossless: bool,
+}
+
+/// Could not save the image
+#[derive(thiserror::Error, Debug)]
+pub enum SaveError {
+    /// The file extension does not correspond to any supported format
+    #[error("Unknown file extension `.{0}`. Supported extensions are: {exts}", exts = ImageFormat::all_extensions())]
+    UnknownExtension(String),
+    /// `image` can only encode lossless WebP images
+    #[error(
+        "Lossy WebP images are not supported. Set `webp-lossless #true` or use a different format"
+    )]
+    LossyWebp,
+    /// JPEG quality must be in range `1..=100`
+    #[error("JPEG quality must be between 1 and 100, but it is {0}")]
+    JpegQuality(u8),
+    /// Failed to encode the image
+    #[error("Failed to encode the image: {0}")]
+    Encode(#[from] image::ImageError),
+    /// Failed to write the image to the file
+    #[error("Failed to write the image: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ImageFormat {
+    /// The file extension used for this format
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+            Self::Avif => "avif",
+            Self::Qoi => "qoi",
+            Self::Bmp => "bmp",
+        }
+    }
+
+    /// All the file extensions that are accepted for this format
+    pub const fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Png => &["png"],
+            Self::Jpeg => &["jpg", "jpeg"],
+            Self::Webp => &["webp"],
+            Self::Avif => &["avif"],
+            Self::Qoi => &["qoi"],
+            Self::Bmp => &["bmp"],
+        }
+    }
+
+    /// Human-readable name of the format
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Webp => "WebP",
+            Self::Avif => "AVIF",
+            Self::Qoi => "QOI",
+            Self::Bmp => "BMP",
+        }
+    }
+
+    /// Comma-separated list of every supported file extension
+    fn all_extensions() -> String {
+        Self::iter()
+            .flat_map(|format| format.extensions().iter().copied())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Find the format which uses this file extension. Case-insensitive
+    pub fn from_extension(extension: &str) -> Result<Self, SaveError> {
+        Self::iter()
+            .find(|format| {
+                format
+                    .extensions()
+                    .iter()
+                    .any(|ext| ext.eq_ignore_ascii_case(extension))
+            })
+            .ok_or_else(|| SaveError::UnknownExtension(extension.to_string()))
+    }
+
+    /// Determine the format from the extension of the path
+    ///
+    /// - If the path has no extension, `None` is returned
+    /// - If the extension is unknown, it is an error
+    pub fn from_path(path: &Path) -> Result<Option<Self>, SaveError> {
+        path.extension()
+            .map(|ext| Self::from_extension(&ext.to_string_lossy()))
+            .transpose()
+    }
+
+    /// Check that an image can be encoded in this format with the given options
+    pub const fn validate(self, options: &EncodeOptions) -> Result<(), SaveError> {
+        match self {
+            Self::Webp if !options.webp_lossless => Err(SaveError::LossyWebp),
+            Self::Jpeg if options.jpeg_quality == 0 || options.jpeg_quality > 100 => {
+                Err(SaveError::JpegQuality(options.jpeg_quality))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Encode the image in this format
+    pub fn encode(
+        self,
+        image: &DynamicImage,
+        options: &EncodeOptions,
+    ) -> Result<Vec<u8>, SaveError> {
+        self.validate(options)?;
+
+        let mut bytes = Cursor::new(Vec::new());
+
+        match self {
+            Self::Png => image.write_with_encoder(PngEncoder::new_with_quality(
+                &mut bytes,
+                match options.png_compression {
+                    PngCompression::Fast => CompressionType::Fast,
+                    PngCompression::Default => CompressionType::Default,
+                    PngCompression::Best => CompressionType::Best,
+                },
+                FilterType::Adaptive,
+            ))?,
+            // JPEG does not support transparency
+            Self::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(
+                image::codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut bytes,
+                    options.jpeg_quality,
+                ),
+            )?,
+            Self::Webp => image
+                .write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(&mut bytes))?,
+            Self::Avif => image.write_to(&mut bytes, image::ImageFormat::Avif)?,
+            Self::Qoi => image.write_to(&mut bytes, image::ImageFormat::Qoi)?,
+            Self::Bmp => image.write_to(&mut bytes, image::ImageFormat::Bmp)?,
+        }
+
+        Ok(bytes.into_inner())
+    }
+}
+
+/// Save the image to the path.
+///
+/// The format is determined from the extension of the path. If the path does not
+/// have an extension, the `fallback` format is used and its extension is appended to the path.
+///
+/// # Returns
+///
+/// The path where the image was actually saved
+pub fn save(
+    image: &DynamicImage,
+    path: &Path,
+    fallback: ImageFormat,
+    options: &EncodeOptions,
+) -> Result<PathBuf, SaveError> {
+    let (format, path) = match ImageFormat::from_path(path)? {
+        Some(format) => (format, path.to_path_buf()),
+        None => (fallback, path.with_extension(fallback.extension())),
+    };
+
+    std::fs::write(&path, format.encode(image, options)?)?;
+
+    Ok(path)
+}
+
+/// Open the file dialog to let the user choose where to save the image, then save it.
+///
+/// The formats are offered as filters in the file dialog, with the configured `format` first.
+/// The dialog does not block the thread, so the app can keep running while it is open.
+///
+/// # Returns
+///
+/// The path where the image was saved, or `None` if the file dialog was closed
+pub async fn save_with_file_dialog(image: &DynamicImage) -> Result<Option<PathBuf>, SaveError> {
+    let format = crate::CONFIG.format;
+
+    let dialog = std::iter::once(format)
+        .chain(ImageFormat::iter().filter(|&other| other != format))
+        .fold(
+            rfd::AsyncFileDialog::new()
+                .set_title("Save Screenshot")
+                .set_file_name(format!("screenshot.{}", format.extension())),
+            |dialog, format| dialog.add_filter(format.name(), format.extensions()),
+        );
+
+    dialog
+        .save_file()
+        .await
+        .map(|file| save(image, file.path(), format, &crate::CONFIG.encode_options()))
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Options which can encode every format
+    const OPTIONS: EncodeOptions = EncodeOptions {
+        jpeg_quality: 90,
+        png_compression: PngCompression::Default,
+        webp_lossless: true,
+    };
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("a/b.PNG")).ok(),
+            Some(Some(ImageFormat::Png)),
+            "extensions are case-insensitive"
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("b.jpeg")).ok(),
+            Some(Some(ImageFormat::Jpeg)),
+            "alternative extension"
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("screenshot")).ok(),
+            Some(None),
+            "no extension"
+        );
+        assert!(
+            matches!(
+                ImageFormat::from_path(Path::new("screenshot.txt")),
+                Err(SaveError::UnknownExtension(ext)) if ext == "txt"
+            ),
+            "unknown extension"
+        );
+    }
+
+    #[test]
+    fn encode_every_format() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            4,
+            3,
+            image::Rgba([255, 0, 0, 255]),
+        ));
+
+        for format in ImageFormat::iter() {
+            let bytes = format
+                .encode(&image, &OPTIONS)
+                .unwrap_or_else(|err| panic!("failed to encode {}: {err}", format.name()));
+
+            // decoding AVIF is not supported
+            if format != ImageFormat::Avif {
+                let decoded = image::load_from_memory(&bytes)
+                    .unwrap_or_else(|err| panic!("failed to decode {}: {err}", format.name()));
+                assert_eq!(
+                    (decoded.width(), decoded.height()),
+                    (4, 3),
+                    "size of the decoded {} image",
+                    format.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::new(1, 1));
+
+        assert!(
+            matches!(
+                ImageFormat::Webp.encode(
+                    &image,
+                    &EncodeOptions {
+                        webp_lossless: false,
+                        ..OPTIONS
+                    }
+                ),
+                Err(SaveError::LossyWebp)
+            ),
+            "lossy webp is not supported"
+        );
+        assert!(
+            matches!(
+                ImageFormat::Jpeg.encode(
+                    &image,
+                    &EncodeOptions {
+                        jpeg_quality: 0,
+                        ..OPTIONS
+                    }
+                ),
+                Err(SaveError::JpegQuality(0))
+            ),
+            "quality must be at least 1"
+        );
+    }
+}
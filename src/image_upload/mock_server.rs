@@ -0,0 +1,180 @@
+//! A tiny HTTP server which responds to a fixed sequence of requests, so that uploading can
+//! be tested without access to the internet
+
+use std::{
+    io::{BufRead as _, BufReader, Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// How the `MockServer` replies to a request
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// Respond with this status and body
+    Respond(u16, String),
+    /// Read the request, but keep the client waiting for a few seconds without responding
+    Hang,
+}
+
+impl Reply {
+    /// Respond with this status and body
+    pub fn respond(status: u16, body: &str) -> Self {
+        Self::Respond(status, body.to_string())
+    }
+}
+
+/// A request received by the `MockServer`
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// HTTP method, e.g. `POST`
+    pub method: String,
+    /// Path which the request was sent to, e.g. `/upload`
+    pub path: String,
+    /// Headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// Body of the request. Lossily converted to UTF-8
+    pub body: String,
+}
+
+impl Request {
+    /// Value of the header with this lowercase name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Replies to the requests it receives in order, one reply per request
+#[derive(Debug)]
+pub struct MockServer {
+    /// Port of the server on `127.0.0.1`
+    port: u16,
+    /// Receives each request once the server has read it
+    request: mpsc::Receiver<Request>,
+}
+
+impl MockServer {
+    /// Start the server on a random port in a background thread.
+    /// It responds to the first request with this status and body
+    pub fn start(status: u16, body: &str) -> Self {
+        Self::with_replies(vec![Reply::respond(status, body)])
+    }
+
+    /// Start the server on a random port in a background thread.
+    /// It replies to each request with the next reply
+    pub fn with_replies(replies: Vec<Reply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind to a random port");
+        let port = listener
+            .local_addr()
+            .expect("listener has an address")
+            .port();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for reply in replies {
+                let (mut stream, _) = listener.accept().expect("accept the connection");
+                let request = read_request(&stream);
+
+                match reply {
+                    Reply::Respond(status, body) => {
+                        write!(
+                            stream,
+                            "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .expect("write the response");
+                    }
+                    // the connection stays open until the spawned thread finishes
+                    Reply::Hang => {
+                        thread::spawn(move || {
+                            thread::sleep(Duration::from_secs(5));
+                            drop(stream);
+                        });
+                    }
+                }
+
+                let _ = sender.send(request);
+            }
+        });
+
+        Self {
+            port,
+            request: receiver,
+        }
+    }
+
+    /// Full URL of the path on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{path}", self.port)
+    }
+
+    /// The next request which the server received
+    pub fn request(&self) -> Request {
+        self.request.recv().expect("the server received a request")
+    }
+}
+
+/// Read the request from the stream
+fn read_request(stream: &TcpStream) -> Request {
+    let mut reader = BufReader::new(stream.try_clone().expect("clone the stream"));
+
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .expect("read the request line");
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("read a header");
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let is_chunked = headers
+        .iter()
+        .any(|(name, value)| name == "transfer-encoding" && value == "chunked");
+
+    let mut body = Vec::new();
+    if is_chunked {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).expect("read the chunk size");
+            let size = usize::from_str_radix(size.trim(), 16).expect("valid chunk size");
+            // each chunk is followed by `\r\n`
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).expect("read the chunk");
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    } else {
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or_default();
+        body.resize(content_length, 0);
+        reader.read_exact(&mut body).expect("read the request body");
+    }
+
+    Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }
+}
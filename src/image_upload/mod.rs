@@ -0,0 +1,660 @@
+//! Upload images to the internet
+//!
+//! Images can be uploaded to any provider declared in the `upload-providers` section of the config.
+//! The image is sent as a multipart form or as the whole body, and the link to it is then
+//! extracted from the response. See `retry.rs` for what happens when a provider fails.
+
+#[cfg(test)]
+pub mod mock_server;
+mod provider;
+mod retry;
+mod sxcu;
+mod template;
+
+use std::time::Duration;
+
+use iced::futures::StreamExt as _;
+
+pub use provider::{
+    DeletionMethod, HttpMethod, Pair, RequestBody, Response, SxcuFile, UploadProvider,
+    UploadProviders,
+};
+pub use retry::{OnEvent, RetryPolicy, UploadEvent, upload_with_fallbacks};
+pub use template::{LinkTemplate, TemplateError};
+
+/// Size of the chunks in which the image is sent, so that progress can be reported
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Could not upload the image
+#[derive(thiserror::Error, Debug)]
+pub enum UploadError {
+    /// There is no provider with this name in the config
+    #[error("There is no upload provider named `{0}`")]
+    UnknownProvider(String),
+    /// The provider specifies more than one of `json-pointer`, `regex` and `link`
+    #[error("Upload provider `{0}` must specify only one of `json-pointer`, `regex` and `link`")]
+    AmbiguousResponse(String),
+    /// The regex of the provider is invalid
+    #[error("Invalid regex for upload provider `{provider}`: {err}")]
+    InvalidRegex {
+        /// Name of the provider
+        provider: String,
+        /// Why the regex is invalid
+        err: regex::Error,
+    },
+    /// The `link` or `deletion-link` template of the provider is invalid
+    #[error("Invalid template for upload provider `{provider}`: {err}")]
+    InvalidTemplate {
+        /// Name of the provider
+        provider: String,
+        /// Why the template is invalid
+        err: TemplateError,
+    },
+    /// Could not create the HTTP client, because of invalid settings in the config
+    #[error(transparent)]
+    Client(#[from] crate::http::ClientError),
+    /// Could not send the request, or receive the response
+    #[error("Failed to upload the image: {}", crate::http::describe_error(.0))]
+    Request(#[from] reqwest::Error),
+    /// The provider responded with an error
+    #[error("Upload failed with status {status}: {body}")]
+    Status {
+        /// HTTP status code of the response
+        status: reqwest::StatusCode,
+        /// Body of the response
+        body: String,
+    },
+    /// The response was expected to be JSON, but it is not
+    #[error("The response is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The JSON pointer does not point to a string in the response
+    #[error("The response has no string at JSON pointer `{0}`")]
+    JsonPointer(String),
+    /// The regex did not match the response
+    #[error("The regex `{0}` does not match the response")]
+    NoMatch(String),
+    /// The link is empty
+    #[error("The response does not contain a link")]
+    EmptyLink,
+    /// There are no providers to upload to
+    #[error("There are no upload providers to upload to")]
+    NoProviders,
+    /// Every provider failed. Contains the name of each provider and its error
+    #[error(
+        "Every upload provider failed:{}",
+        .0.iter().map(|(provider, err)| format!("\n- {provider}: {err}")).collect::<String>()
+    )]
+    AllFailed(Vec<(String, UploadError)>),
+}
+
+impl crate::Config {
+    /// Find the upload provider with this name.
+    ///
+    /// If several have the same name, the last one wins, so that
+    /// the user's providers override the default ones
+    pub fn upload_provider(&self, name: &str) -> Result<&UploadProvider, UploadError> {
+        self.upload_providers
+            .iter()
+            .rev()
+            .find(|provider| provider.name == name)
+            .ok_or_else(|| UploadError::UnknownProvider(name.to_string()))
+    }
+
+    /// The `default-image-upload-provider`, followed by the fallback providers.
+    ///
+    /// Unknown fallback providers are skipped, so that a typo in one of them does not
+    /// prevent uploading to the others. `--check-config` reports them
+    pub fn upload_providers_in_order(&self) -> Result<Vec<&UploadProvider>, UploadError> {
+        let mut providers = vec![self.upload_provider(&self.default_image_upload_provider)?];
+        for name in &self.fallback_upload_providers {
+            match self.upload_provider(name) {
+                Ok(provider) => {
+                    if !providers.iter().any(|other| other.name == provider.name) {
+                        providers.push(provider);
+                    }
+                }
+                Err(err) => log::warn!("Skipping fallback upload provider: {err}"),
+            }
+        }
+
+        Ok(providers)
+    }
+
+    /// Names in `upload-providers { fallback }` which are not the name of any upload provider
+    pub fn unknown_fallback_upload_providers(&self) -> impl Iterator<Item = &str> {
+        self.fallback_upload_providers
+            .iter()
+            .map(String::as_str)
+            .filter(|name| self.upload_provider(name).is_err())
+    }
+}
+
+/// An image encoded in a file format, ready to be uploaded.
+///
+/// It only lives in memory, so no copy of the screenshot is left behind on the disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedImage {
+    /// Contents of the file
+    pub bytes: Vec<u8>,
+    /// Format of the file
+    pub format: image::ImageFormat,
+}
+
+impl EncodedImage {
+    /// Encode the image as PNG
+    pub fn png(image: &image::DynamicImage) -> Result<Self, image::ImageError> {
+        let mut bytes = Vec::new();
+        image.write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )?;
+
+        Ok(Self {
+            bytes,
+            format: image::ImageFormat::Png,
+        })
+    }
+
+    /// Name of the file sent to providers which want one
+    fn file_name(&self) -> String {
+        format!(
+            "ferrishot-screenshot.{}",
+            self.format.extensions_str().first().unwrap_or(&"png")
+        )
+    }
+}
+
+/// A request body which reports how much of it was sent
+fn progress_body(bytes: &[u8], on_event: &OnEvent) -> reqwest::Body {
+    let total = bytes.len() as u64;
+    let on_event = std::sync::Arc::clone(on_event);
+    let chunks = bytes
+        .chunks(CHUNK_SIZE)
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+
+    let mut sent = 0;
+    reqwest::Body::wrap_stream(iced::futures::stream::iter(chunks).map(move |chunk| {
+        sent += chunk.len() as u64;
+        on_event(UploadEvent::Progress { sent, total });
+        Ok::<_, std::io::Error>(chunk)
+    }))
+}
+
+/// The image was uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uploaded {
+    /// Link to the uploaded image
+    pub link: String,
+    /// Visiting this link deletes the image, if the provider supports it
+    pub deletion_link: Option<String>,
+}
+
+impl UploadProvider {
+    /// Upload the image to this provider, giving up if it takes longer than the `timeout`
+    pub async fn upload(
+        &self,
+        client: &reqwest::Client,
+        image: &EncodedImage,
+        timeout: Duration,
+        on_event: &OnEvent,
+    ) -> Result<Uploaded, UploadError> {
+        // check these before sending anything
+        let link = self.link_template()?;
+        let deletion_link = self
+            .response
+            .deletion_link
+            .as_deref()
+            .map(|template| self.parse_template(template))
+            .transpose()?;
+
+        let size = image.bytes.len() as u64;
+        let body = progress_body(&image.bytes, on_event);
+
+        let request = client
+            .request(self.method.into(), &self.url)
+            .timeout(timeout)
+            .query(
+                &self
+                    .query
+                    .iter()
+                    .map(|Pair { name, value }| (name, value))
+                    .collect::<Vec<_>>(),
+            );
+
+        let request = match self.body {
+            RequestBody::Multipart => {
+                let part = reqwest::multipart::Part::stream_with_length(body, size)
+                    .file_name(image.file_name())
+                    .mime_str(image.format.to_mime_type())?;
+
+                request.multipart(self.form.iter().fold(
+                    reqwest::multipart::Form::new().part(self.field.clone(), part),
+                    |form, Pair { name, value }| form.text(name.clone(), value.clone()),
+                ))
+            }
+            RequestBody::Binary => request
+                .header(reqwest::header::CONTENT_LENGTH, size)
+                .body(body),
+        };
+
+        let request = self
+            .headers
+            .iter()
+            .fold(request, |request, Pair { name, value }| {
+                request.header(name, value)
+            });
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(UploadError::Status { status, body });
+        }
+
+        Ok(Uploaded {
+            link: link.render(&body)?,
+            // the image was uploaded, so it's not worth failing just because of this
+            deletion_link: deletion_link.and_then(|template| {
+                template
+                    .render(&body)
+                    .inspect_err(|err| log::warn!("Could not find the deletion link: {err}"))
+                    .ok()
+            }),
+        })
+    }
+
+    /// Delete an image which was uploaded to this provider by requesting its deletion link.
+    ///
+    /// The headers of the provider are sent too, as they may be needed for authorization
+    pub async fn delete(
+        &self,
+        client: &reqwest::Client,
+        deletion_link: &str,
+    ) -> Result<(), UploadError> {
+        let request = client.request(self.response.deletion_method.into(), deletion_link);
+
+        let response = self
+            .headers
+            .iter()
+            .fold(request, |request, Pair { name, value }| {
+                request.header(name, value)
+            })
+            .send()
+            .await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(UploadError::Status {
+                status,
+                body: response.text().await?,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Parse a template of this provider
+    fn parse_template(&self, template: &str) -> Result<LinkTemplate, UploadError> {
+        LinkTemplate::parse(template).map_err(|err| UploadError::InvalidTemplate {
+            provider: self.name.clone(),
+            err,
+        })
+    }
+
+    /// How to find the link in the response of this provider
+    fn link_template(&self) -> Result<LinkTemplate, UploadError> {
+        let Response {
+            json_pointer,
+            regex,
+            link,
+            ..
+        } = &self.response;
+
+        match (json_pointer, regex, link) {
+            (Some(pointer), None, None) => Ok(LinkTemplate::json_pointer(pointer)),
+            (None, Some(regex), None) => {
+                LinkTemplate::regex(regex).map_err(|err| UploadError::InvalidRegex {
+                    provider: self.name.clone(),
+                    err,
+                })
+            }
+            (None, None, Some(link)) => self.parse_template(link),
+            (None, None, None) => Ok(LinkTemplate::response()),
+            _ => Err(UploadError::AmbiguousResponse(self.name.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::mock_server::MockServer;
+    use super::*;
+
+    /// A provider which uploads to the mock server
+    fn provider(server: &MockServer) -> UploadProvider {
+        UploadProvider {
+            name: String::from("mock"),
+            url: server.url("/upload"),
+            method: HttpMethod::Post,
+            field: String::from("file"),
+            body: RequestBody::Multipart,
+            query: Vec::new(),
+            headers: Vec::new(),
+            form: Vec::new(),
+            response: Response::default(),
+        }
+    }
+
+    /// Upload a small image to the provider
+    async fn upload(provider: &UploadProvider) -> Result<Uploaded, UploadError> {
+        provider
+            .upload(
+                &reqwest::Client::new(),
+                &EncodedImage {
+                    bytes: b"not really a png".to_vec(),
+                    format: image::ImageFormat::Png,
+                },
+                Duration::from_secs(10),
+                &(std::sync::Arc::new(|_| ()) as OnEvent),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn plain_body() {
+        let server = MockServer::start(200, "https://example.com/a.png\n");
+
+        assert_eq!(
+            upload(&provider(&server))
+                .await
+                .ok()
+                .map(|uploaded| uploaded.link)
+                .as_deref(),
+            Some("https://example.com/a.png"),
+            "the whole body is the link, without whitespace"
+        );
+
+        let request = server.request();
+        assert_eq!(request.method, "POST", "default method");
+        assert_eq!(request.path, "/upload", "path of the url");
+        assert!(
+            request.body.contains(r#"name="file""#),
+            "image is sent in the `file` field"
+        );
+        assert!(
+            request.body.contains("not really a png"),
+            "contents of the image are sent"
+        );
+        assert!(
+            request
+                .body
+                .contains(r#"filename="ferrishot-screenshot.png""#)
+                && request.body.contains("image/png"),
+            "name and type of the image are sent"
+        );
+    }
+
+    #[tokio::test]
+    async fn headers_and_form_fields() {
+        let server = MockServer::start(200, "https://example.com/a.png");
+
+        let provider = UploadProvider {
+            method: HttpMethod::Put,
+            field: String::from("image"),
+            headers: vec![Pair {
+                name: String::from("Authorization"),
+                value: String::from("Bearer 1234"),
+            }],
+            form: vec![Pair {
+                name: String::from("expires"),
+                value: String::from("24"),
+            }],
+            ..provider(&server)
+        };
+
+        upload(&provider).await.expect("upload succeeds");
+
+        let request = server.request();
+        assert_eq!(request.method, "PUT", "custom method");
+        assert_eq!(
+            request.header("authorization"),
+            Some("Bearer 1234"),
+            "custom header"
+        );
+        assert!(
+            request.body.contains(r#"name="image""#),
+            "image is sent in the custom field"
+        );
+        assert!(
+            request.body.contains(r#"name="expires""#) && request.body.contains("24"),
+            "custom form field"
+        );
+    }
+
+    #[tokio::test]
+    async fn json_pointer() {
+        let server = MockServer::start(200, r#"{"data": {"link": "https://example.com/b.png"}}"#);
+        let provider = UploadProvider {
+            response: Response {
+                json_pointer: Some(String::from("/data/link")),
+                ..Response::default()
+            },
+            ..provider(&server)
+        };
+
+        assert_eq!(
+            upload(&provider)
+                .await
+                .ok()
+                .map(|uploaded| uploaded.link)
+                .as_deref(),
+            Some("https://example.com/b.png"),
+            "link is found at the pointer"
+        );
+    }
+
+    #[tokio::test]
+    async fn regex() {
+        let server = MockServer::start(200, "<a href='https://example.com/c.png'>your image</a>");
+        let provider = UploadProvider {
+            response: Response {
+                regex: Some(String::from("href='([^']+)'")),
+                ..Response::default()
+            },
+            ..provider(&server)
+        };
+
+        assert_eq!(
+            upload(&provider)
+                .await
+                .ok()
+                .map(|uploaded| uploaded.link)
+                .as_deref(),
+            Some("https://example.com/c.png"),
+            "link is the first capture group"
+        );
+    }
+
+    #[tokio::test]
+    async fn error_status() {
+        let server = MockServer::start(500, "the server is on fire");
+
+        assert!(
+            matches!(
+                upload(&provider(&server)).await,
+                Err(UploadError::Status { status, body })
+                    if status == reqwest::StatusCode::INTERNAL_SERVER_ERROR
+                        && body == "the server is on fire"
+            ),
+            "unsuccessful status is an error"
+        );
+    }
+
+    #[test]
+    fn parse_provider() {
+        let providers = knus::parse::<Vec<UploadProvider>>(
+            "config.kdl",
+            r#"
+            provider my-host url="https://img.example.com/upload" method=put field=image {
+              header Authorization "Bearer 1234"
+              form expires "24"
+              response json-pointer="/data/link"
+            }
+            provider minimal url="https://example.com"
+            "#,
+        )
+        .expect("valid providers");
+
+        assert_eq!(
+            providers,
+            [
+                UploadProvider {
+                    name: String::from("my-host"),
+                    url: String::from("https://img.example.com/upload"),
+                    method: HttpMethod::Put,
+                    field: String::from("image"),
+                    body: RequestBody::Multipart,
+                    query: Vec::new(),
+                    headers: vec![Pair {
+                        name: String::from("Authorization"),
+                        value: String::from("Bearer 1234"),
+                    }],
+                    form: vec![Pair {
+                        name: String::from("expires"),
+                        value: String::from("24"),
+                    }],
+                    response: Response {
+                        json_pointer: Some(String::from("/data/link")),
+                        ..Response::default()
+                    },
+                },
+                UploadProvider {
+                    name: String::from("minimal"),
+                    url: String::from("https://example.com"),
+                    method: HttpMethod::Post,
+                    field: String::from("file"),
+                    body: RequestBody::Multipart,
+                    query: Vec::new(),
+                    headers: Vec::new(),
+                    form: Vec::new(),
+                    response: Response::default(),
+                },
+            ],
+            "all options, and defaults"
+        );
+    }
+
+    #[tokio::test]
+    async fn binary_body_and_templates() {
+        let server = MockServer::start(200, r#"{"id": "abc", "delete": "xyz"}"#);
+        let provider = UploadProvider {
+            body: RequestBody::Binary,
+            query: vec![Pair {
+                name: String::from("key"),
+                value: String::from("1234"),
+            }],
+            response: Response {
+                link: Some(String::from("https://i.example.com/{json:/id}.png")),
+                deletion_link: Some(String::from("https://i.example.com/delete/{json:/delete}")),
+                ..Response::default()
+            },
+            ..provider(&server)
+        };
+
+        assert_eq!(
+            upload(&provider).await.ok(),
+            Some(Uploaded {
+                link: String::from("https://i.example.com/abc.png"),
+                deletion_link: Some(String::from("https://i.example.com/delete/xyz")),
+            }),
+            "link and deletion link are built from the templates"
+        );
+
+        let request = server.request();
+        assert_eq!(request.path, "/upload?key=1234", "query parameters");
+        assert_eq!(
+            request.body, "not really a png",
+            "the body is just the image"
+        );
+    }
+
+    #[test]
+    fn ambiguous_response() {
+        let server = MockServer::start(200, "");
+        let provider = UploadProvider {
+            response: Response {
+                json_pointer: Some(String::from("/link")),
+                regex: Some(String::from(".*")),
+                ..Response::default()
+            },
+            ..provider(&server)
+        };
+
+        assert!(
+            matches!(
+                provider.link_template(),
+                Err(UploadError::AmbiguousResponse(name)) if name == "mock"
+            ),
+            "only one way to find the link can be used"
+        );
+    }
+
+    #[test]
+    fn extract_errors() {
+        assert!(
+            matches!(
+                LinkTemplate::json_pointer("/link").render(r#"{"url": "x"}"#),
+                Err(UploadError::JsonPointer(_))
+            ),
+            "missing JSON pointer"
+        );
+        assert!(
+            matches!(
+                LinkTemplate::response().render("  \n"),
+                Err(UploadError::EmptyLink)
+            ),
+            "empty response"
+        );
+        assert!(
+            matches!(
+                LinkTemplate::parse("{json:/id}")
+                    .expect("valid template")
+                    .render(r#"{"id": " "}"#),
+                Err(UploadError::EmptyLink)
+            ),
+            "empty link rendered from a template"
+        );
+    }
+
+    #[tokio::test]
+    async fn delete() {
+        let server = MockServer::start(200, "deleted");
+        let provider = UploadProvider {
+            headers: vec![Pair {
+                name: String::from("Authorization"),
+                value: String::from("Bearer 1234"),
+            }],
+            response: Response {
+                deletion_method: DeletionMethod::Delete,
+                ..Response::default()
+            },
+            ..provider(&server)
+        };
+
+        provider
+            .delete(&reqwest::Client::new(), &server.url("/delete/xyz"))
+            .await
+            .expect("deletion succeeds");
+
+        let request = server.request();
+        assert_eq!(request.method, "DELETE", "custom deletion method");
+        assert_eq!(request.path, "/delete/xyz", "path of the deletion link");
+        assert_eq!(
+            request.header("authorization"),
+            Some("Bearer 1234"),
+            "headers of the provider are sent"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Upload providers, as declared in the config
+//!
+//! ```kdl
+//! upload-providers {
+//!   provider my-host url="https://img.example.com/upload" method=put field=image {
+//!     header Authorization "Bearer 1234"
+//!     form expires "24"
+//!     response json-pointer="/data/link" deletion-link="{json:/data/delete}" deletion-method=delete
+//!   }
+//!   // ShareX custom uploader
+//!   sxcu "~/sharex/imgur.sxcu" name=imgur
+//!   // tried in order when the default provider fails
+//!   fallback imgur the-null-pointer
+//! }
+//! ```
+
+use std::path::Path;
+
+/// Upload providers declared in the config
+#[derive(knus::Decode, Debug, Default, Clone)]
+pub struct UploadProviders {
+    /// A list of providers, directly as read from the config file
+    #[knus(children(name = "provider"))]
+    pub providers: Vec<UploadProvider>,
+    /// ShareX custom uploader files to load providers from
+    #[knus(children(name = "sxcu"))]
+    pub sxcu: Vec<SxcuFile>,
+    /// Providers to try in order when the `default-image-upload-provider` fails
+    #[knus(child)]
+    pub fallback: Option<Fallback>,
+}
+
+/// Names of the providers to try in order when the `default-image-upload-provider` fails
+///
+/// ```kdl
+/// fallback catbox the-null-pointer
+/// ```
+#[derive(knus::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Fallback {
+    /// Names of the providers
+    #[knus(arguments)]
+    pub providers: Vec<String>,
+}
+
+impl UploadProviders {
+    /// All of the providers, including the ones loaded from `.sxcu` files.
+    ///
+    /// Relative paths of `.sxcu` files are relative to `config_dir`
+    pub fn resolve(self, config_dir: &Path) -> miette::Result<Vec<UploadProvider>> {
+        let mut providers = self.providers;
+
+        for sxcu in self.sxcu {
+            let path = crate::util::expand_home(&sxcu.path)
+                .map_err(|err| miette::miette!("Could not load {}: {err}", sxcu.path))?;
+            providers.push(super::sxcu::load(&config_dir.join(path), sxcu.name)?);
+        }
+
+        Ok(providers)
+    }
+}
+
+/// A ShareX custom uploader file, which describes an upload provider
+#[derive(knus::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct SxcuFile {
+    /// Path to the `.sxcu` file
+    #[knus(argument)]
+    pub path: String,
+    /// Name of the provider. Defaults to the `Name` in the file
+    #[knus(property)]
+    pub name: Option<String>,
+}
+
+/// HTTP method used to upload the image
+#[derive(knus::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HttpMethod {
+    /// `POST`
+    #[default]
+    Post,
+    /// `PUT`
+    Put,
+    /// `PATCH`
+    Patch,
+}
+
+impl From<HttpMethod> for reqwest::Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Post => Self::POST,
+            HttpMethod::Put => Self::PUT,
+            HttpMethod::Patch => Self::PATCH,
+        }
+    }
+}
+
+/// HTTP method used to request the deletion link
+#[derive(knus::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionMethod {
+    /// `GET`, like visiting the link in a browser
+    #[default]
+    Get,
+    /// `DELETE`
+    Delete,
+    /// `POST`
+    Post,
+}
+
+impl From<DeletionMethod> for reqwest::Method {
+    fn from(method: DeletionMethod) -> Self {
+        match method {
+            DeletionMethod::Get => Self::GET,
+            DeletionMethod::Delete => Self::DELETE,
+            DeletionMethod::Post => Self::POST,
+        }
+    }
+}
+
+/// How the image is sent
+#[derive(knus::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RequestBody {
+    /// As a field of a multipart form
+    #[default]
+    Multipart,
+    /// The body is just the image
+    Binary,
+}
+
+/// A name and a value, e.g. for HTTP headers and form fields
+#[derive(knus::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Pair {
+    /// Name of the header or form field
+    #[knus(argument)]
+    pub name: String,
+    /// Its value
+    #[knus(argument)]
+    pub value: String,
+}
+
+/// How to find the link to the image in the response.
+///
+/// If none of `json-pointer`, `regex` and `link` are specified, the whole response is the link
+#[derive(knus::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// The response is JSON, and the link is at this JSON pointer, e.g. `/data/link`
+    ///
+    /// See <https://datatracker.ietf.org/doc/html/rfc6901>
+    #[knus(property)]
+    pub json_pointer: Option<String>,
+    /// The link is the first capture group of this regex, or the whole match
+    /// if there are no capture groups
+    #[knus(property)]
+    pub regex: Option<String>,
+    /// Build the link from a template, e.g. `https://i.example.com/{json:/id}.png`
+    ///
+    /// See `template.rs` for the available placeholders
+    #[knus(property)]
+    pub link: Option<String>,
+    /// Template of the link which deletes the uploaded image
+    #[knus(property)]
+    pub deletion_link: Option<String>,
+    /// HTTP method used to request the deletion link
+    #[knus(default, property)]
+    pub deletion_method: DeletionMethod,
+}
+
+/// A service which the image can be uploaded to
+#[derive(knus::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct UploadProvider {
+    /// Name of the provider, used to refer to it from `default-image-upload-provider`
+    #[knus(argument)]
+    pub name: String,
+    /// Where to send the image
+    #[knus(property)]
+    pub url: String,
+    /// HTTP method of the request
+    #[knus(default, property)]
+    pub method: HttpMethod,
+    /// Name of the multipart form field which contains the image
+    #[knus(default = String::from("file"), property)]
+    pub field: String,
+    /// How the image is sent
+    #[knus(default, property)]
+    pub body: RequestBody,
+    /// Query parameters to add to the `url`
+    #[knus(children(name = "query"))]
+    pub query: Vec<Pair>,
+    /// Additional headers to send
+    #[knus(children(name = "header"))]
+    pub headers: Vec<Pair>,
+    /// Additional form fields to send
+    #[knus(children(name = "form"))]
+    pub form: Vec<Pair>,
+    /// How to find the link in the response
+    #[knus(default, child)]
+    pub response: Response,
+}
@@ -0,0 +1,352 @@
+//! Retry failed uploads, and fall back to other providers when one keeps failing
+//!
+//! Each provider is tried up to `retries + 1` times, waiting `retry_delay` before the first
+//! retry and twice as long before each one after that. Only errors which are likely to go
+//! away are retried: timeouts, network errors and server errors. A provider which
+//! rate-limits us is not retried, since retrying immediately won't help.
+
+use std::{sync::Arc, time::Duration};
+
+use super::{EncodedImage, UploadError, UploadProvider, Uploaded};
+
+/// Something that happened while uploading, reported so that it can be shown to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadEvent {
+    /// Started uploading to this provider
+    Attempt {
+        /// Name of the provider
+        provider: String,
+        /// Which attempt this is for this provider, starting at 1
+        attempt: u32,
+    },
+    /// Sent this many bytes of the request body
+    Progress {
+        /// Bytes sent so far
+        sent: u64,
+        /// Size of the whole body
+        total: u64,
+    },
+}
+
+/// Called for each `UploadEvent`
+pub type OnEvent = Arc<dyn Fn(UploadEvent) + Send + Sync>;
+
+/// How long to wait for providers, and how often to retry them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Give up on a request which takes longer than this
+    pub timeout: Duration,
+    /// How many times to retry each provider
+    pub retries: u32,
+    /// How long to wait before the first retry. Doubles after each retry
+    pub retry_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The policy as configured by the user
+    pub fn from_config(config: &crate::Config) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.upload_timeout.into()),
+            retries: config.upload_retries,
+            retry_delay: Duration::from_millis(config.upload_retry_delay.into()),
+        }
+    }
+}
+
+impl UploadError {
+    /// The same request might succeed if we try it again
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Request(err) => err.is_timeout() || err.is_connect() || err.is_request(),
+            Self::Status { status, .. } => {
+                status.is_server_error() || *status == reqwest::StatusCode::REQUEST_TIMEOUT
+            }
+            _ => false,
+        }
+    }
+
+    /// The provider refuses to accept more uploads for now
+    fn is_rate_limit(&self) -> bool {
+        matches!(
+            self,
+            Self::Status { status, .. } if *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        )
+    }
+}
+
+/// Upload the image to the first provider which accepts it, trying them in order
+///
+/// Returns the provider that the image was uploaded to
+pub async fn upload_with_fallbacks<'a>(
+    providers: &[&'a UploadProvider],
+    client: &reqwest::Client,
+    image: &EncodedImage,
+    policy: RetryPolicy,
+    on_event: &OnEvent,
+) -> Result<(&'a UploadProvider, Uploaded), UploadError> {
+    let mut failures = Vec::new();
+
+    for &provider in providers {
+        let mut attempt = 1;
+
+        let err = loop {
+            on_event(UploadEvent::Attempt {
+                provider: provider.name.clone(),
+                attempt,
+            });
+
+            let err = match provider
+                .upload(client, image, policy.timeout, on_event)
+                .await
+            {
+                Ok(uploaded) => return Ok((provider, uploaded)),
+                Err(err) => err,
+            };
+
+            if err.is_rate_limit() {
+                log::warn!("Upload provider `{}` is rate limiting us", provider.name);
+                break err;
+            }
+
+            if !err.is_transient() || attempt > policy.retries {
+                break err;
+            }
+
+            let delay = policy
+                .retry_delay
+                .saturating_mul(1 << (attempt - 1).min(16));
+            log::warn!(
+                "Upload to `{}` failed, retrying in {delay:?}: {err}",
+                provider.name
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
+
+        failures.push((provider.name.clone(), err));
+    }
+
+    match failures.len() {
+        0 => Err(UploadError::NoProviders),
+        1 => Err(failures.remove(0).1),
+        _ => Err(UploadError::AllFailed(failures)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::super::mock_server::{MockServer, Reply};
+    use super::super::{HttpMethod, RequestBody, Response};
+    use super::*;
+
+    /// A policy which doesn't make the tests slow
+    const POLICY: RetryPolicy = RetryPolicy {
+        timeout: Duration::from_millis(500),
+        retries: 2,
+        retry_delay: Duration::from_millis(10),
+    };
+
+    /// A provider named `name` which uploads to the mock server
+    fn provider(name: &str, server: &MockServer) -> UploadProvider {
+        UploadProvider {
+            name: name.to_string(),
+            url: server.url("/upload"),
+            method: HttpMethod::Post,
+            field: String::from("file"),
+            body: RequestBody::Multipart,
+            query: Vec::new(),
+            headers: Vec::new(),
+            form: Vec::new(),
+            response: Response::default(),
+        }
+    }
+
+    /// Upload a small image to the providers, returning the name of the provider that
+    /// accepted it and the events which happened
+    async fn upload(
+        providers: &[&UploadProvider],
+        policy: RetryPolicy,
+    ) -> (Result<String, UploadError>, Vec<UploadEvent>) {
+        let image = EncodedImage {
+            bytes: b"not really a png".to_vec(),
+            format: image::ImageFormat::Png,
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let on_event: OnEvent = {
+            let events = Arc::clone(&events);
+            Arc::new(move |event| events.lock().expect("not poisoned").push(event))
+        };
+
+        let result = upload_with_fallbacks(
+            providers,
+            &reqwest::Client::new(),
+            &image,
+            policy,
+            &on_event,
+        )
+        .await
+        .map(|(provider, _)| provider.name.clone());
+
+        let events = events.lock().expect("not poisoned").clone();
+        (result, events)
+    }
+
+    /// Only the `Attempt` events, as `(provider, attempt)`
+    fn attempts(events: &[UploadEvent]) -> Vec<(&str, u32)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                UploadEvent::Attempt { provider, attempt } => Some((provider.as_str(), *attempt)),
+                UploadEvent::Progress { .. } => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn progress() {
+        let server = MockServer::start(200, "https://example.com/a.png");
+
+        let (result, events) = upload(&[&provider("a", &server)], POLICY).await;
+
+        assert_eq!(result.ok().as_deref(), Some("a"), "upload succeeds");
+        let Some(UploadEvent::Progress { sent, total }) = events.last() else {
+            panic!("the last event is progress, got {events:?}");
+        };
+        assert!(*total > 0, "size of the body is known");
+        assert_eq!(sent, total, "the whole body was sent");
+    }
+
+    #[tokio::test]
+    async fn retry_server_errors() {
+        let server = MockServer::with_replies(vec![
+            Reply::respond(500, "the server is on fire"),
+            Reply::respond(503, "the fire is being put out"),
+            Reply::respond(200, "https://example.com/a.png"),
+        ]);
+
+        let (result, events) = upload(&[&provider("a", &server)], POLICY).await;
+
+        assert_eq!(result.ok().as_deref(), Some("a"), "third attempt succeeds");
+        assert_eq!(
+            attempts(&events),
+            [("a", 1), ("a", 2), ("a", 3)],
+            "retried twice"
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_timeout() {
+        let server = MockServer::with_replies(vec![
+            Reply::Hang,
+            Reply::respond(200, "https://example.com/a.png"),
+        ]);
+
+        let (result, events) = upload(
+            &[&provider("a", &server)],
+            RetryPolicy {
+                timeout: Duration::from_millis(200),
+                ..POLICY
+            },
+        )
+        .await;
+
+        assert_eq!(
+            result.ok().as_deref(),
+            Some("a"),
+            "retry succeeds after a timeout"
+        );
+        assert_eq!(attempts(&events), [("a", 1), ("a", 2)], "retried once");
+    }
+
+    #[tokio::test]
+    async fn fall_back_when_rate_limited() {
+        let rate_limited = MockServer::start(429, "slow down");
+        let fallback = MockServer::start(200, "https://example.com/a.png");
+
+        let (result, events) = upload(
+            &[
+                &provider("rate-limited", &rate_limited),
+                &provider("fallback", &fallback),
+            ],
+            POLICY,
+        )
+        .await;
+
+        assert_eq!(
+            result.ok().as_deref(),
+            Some("fallback"),
+            "the fallback accepted the image"
+        );
+        assert_eq!(
+            attempts(&events),
+            [("rate-limited", 1), ("fallback", 1)],
+            "rate limited provider is not retried"
+        );
+    }
+
+    #[tokio::test]
+    async fn all_providers_fail() {
+        let broken = MockServer::with_replies(vec![
+            Reply::respond(500, "the server is on fire"),
+            Reply::respond(500, "the server is still on fire"),
+        ]);
+        let rejecting = MockServer::start(400, "that's not an image");
+
+        let (result, events) = upload(
+            &[
+                &provider("broken", &broken),
+                &provider("rejecting", &rejecting),
+            ],
+            RetryPolicy {
+                retries: 1,
+                ..POLICY
+            },
+        )
+        .await;
+
+        assert_eq!(
+            attempts(&events),
+            [("broken", 1), ("broken", 2), ("rejecting", 1)],
+            "client errors are not retried"
+        );
+        let Err(UploadError::AllFailed(failures)) = result else {
+            panic!("every provider failed, got {result:?}");
+        };
+        assert_eq!(
+            failures
+                .iter()
+                .map(|(provider, _)| provider.as_str())
+                .collect::<Vec<_>>(),
+            ["broken", "rejecting"],
+            "the error of each provider is kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        let server = MockServer::with_replies(vec![Reply::Hang]);
+        let provider = provider("a", &server);
+
+        let (upload, handle) = iced::futures::future::abortable(upload(
+            &[&provider],
+            RetryPolicy {
+                timeout: Duration::from_secs(10),
+                ..POLICY
+            },
+        ));
+
+        let start = std::time::Instant::now();
+        let (result, ()) = tokio::join!(upload, async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.abort();
+        });
+
+        assert!(result.is_err(), "the upload was aborted");
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "aborting does not wait for the provider"
+        );
+    }
+}
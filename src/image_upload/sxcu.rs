@@ -0,0 +1,488 @@
+//! Load upload providers from ShareX custom uploader (`.sxcu`) files
+//!
+//! See <https://getsharex.com/docs/custom-uploader>
+//!
+//! Only what is needed to upload images is supported: the request URL, method, body type, file form
+//! name, headers, arguments, query parameters and the URL and deletion URL of the uploaded image.
+//! Placeholders are only supported in the URL and deletion URL.
+
+use std::{collections::BTreeMap, path::Path};
+
+use miette::{NamedSource, SourceSpan};
+use serde::Deserialize;
+
+use super::{HttpMethod, LinkTemplate, Pair, RequestBody, Response, UploadProvider};
+
+/// Shown when the `URL` or `DeletionURL` contains a placeholder which we can't handle
+const SUPPORTED_PLACEHOLDERS: &str =
+    "Supported placeholders are `{response}`, `{json:path}` and `{regex:pattern|group}`";
+
+/// Contents of a `.sxcu` file. Only the keys which matter for uploading images
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Sxcu {
+    /// Name of the uploader
+    name: Option<String>,
+    /// HTTP method. Older versions of ShareX call this `RequestType`
+    #[serde(alias = "RequestType")]
+    request_method: Option<String>,
+    /// Where the image is uploaded
+    #[serde(rename = "RequestURL")]
+    request_url: String,
+    /// Query parameters
+    #[serde(default)]
+    parameters: BTreeMap<String, String>,
+    /// HTTP headers
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// Type of the body, e.g. `MultipartFormData`
+    body: Option<String>,
+    /// Additional form fields
+    #[serde(default)]
+    arguments: BTreeMap<String, String>,
+    /// Name of the form field which contains the image
+    file_form_name: Option<String>,
+    /// Link to the uploaded image
+    #[serde(rename = "URL")]
+    url: Option<String>,
+    /// Link which deletes the uploaded image
+    #[serde(rename = "DeletionURL")]
+    deletion_url: Option<String>,
+}
+
+/// The `.sxcu` file is invalid, or uses something that we don't support
+#[derive(thiserror::Error, miette::Diagnostic, Debug)]
+#[error("{message}")]
+pub struct SxcuError {
+    /// What went wrong
+    message: String,
+    /// Contents of the `.sxcu` file
+    #[source_code]
+    source_code: NamedSource<String>,
+    /// Where it went wrong
+    #[label("{label}")]
+    span: SourceSpan,
+    /// Short explanation shown at the `span`
+    label: String,
+    /// How to fix it
+    #[help]
+    help: Option<String>,
+}
+
+/// Contents of a `.sxcu` file, used to point at the problematic parts
+struct Source<'a> {
+    /// Name of the file
+    name: String,
+    /// Contents of the file
+    text: &'a str,
+}
+
+impl Source<'_> {
+    /// Create an error which points at the span
+    fn error(
+        &self,
+        span: SourceSpan,
+        message: impl Into<String>,
+        label: impl Into<String>,
+        help: Option<&str>,
+    ) -> SxcuError {
+        SxcuError {
+            message: message.into(),
+            source_code: NamedSource::new(&self.name, self.text.to_string()),
+            span,
+            label: label.into(),
+            help: help.map(ToString::to_string),
+        }
+    }
+
+    /// Span of the string `value` in the file, starting at `offset` bytes into the value
+    /// and `len` bytes long.
+    ///
+    /// If the value can't be found, the span covers the whole file
+    fn span_of(&self, value: &str, offset: usize, len: usize) -> SourceSpan {
+        // length of the JSON-encoded string, without the quotes
+        let encoded_len =
+            |s: &str| serde_json::to_string(s).map_or(s.len(), |encoded| encoded.len() - 2);
+
+        serde_json::to_string(value)
+            .ok()
+            .and_then(|encoded| self.text.find(&encoded))
+            .map_or_else(
+                || (0, self.text.len()).into(),
+                |start| {
+                    let start = start + 1 + encoded_len(&value[..offset]);
+                    (start, encoded_len(&value[offset..offset + len])).into()
+                },
+            )
+    }
+
+    /// Span of the whole string `value` in the file
+    fn span_of_value(&self, value: &str) -> SourceSpan {
+        self.span_of(value, 0, value.len())
+    }
+}
+
+/// Load the upload provider from the `.sxcu` file.
+///
+/// The name of the provider is `name` if specified, otherwise the name in the file,
+/// or the name of the file
+pub fn load(path: &Path, name: Option<String>) -> miette::Result<UploadProvider> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| miette::miette!("Failed to read {}: {err}", path.display()))?;
+
+    let source = Source {
+        name: path.display().to_string(),
+        text: &text,
+    };
+
+    let default_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(parse(&source, name, default_name)?)
+}
+
+/// Parse the contents of the `.sxcu` file
+fn parse(
+    source: &Source,
+    name: Option<String>,
+    default_name: String,
+) -> Result<UploadProvider, SxcuError> {
+    let sxcu = serde_json::from_str::<Sxcu>(source.text).map_err(|err| {
+        // `serde_json` reports 1-based lines and columns
+        let offset = source
+            .text
+            .split_inclusive('\n')
+            .take(err.line().saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>()
+            + err.column().saturating_sub(1);
+
+        source.error(
+            (offset.min(source.text.len()), 0).into(),
+            "Invalid ShareX custom uploader",
+            err.to_string(),
+            None,
+        )
+    })?;
+
+    let method = sxcu.request_method.as_deref().unwrap_or("POST");
+    let method = match method.to_ascii_uppercase().as_str() {
+        "POST" => HttpMethod::Post,
+        "PUT" => HttpMethod::Put,
+        "PATCH" => HttpMethod::Patch,
+        _ => {
+            return Err(source.error(
+                source.span_of_value(method),
+                format!("Unsupported request method `{method}`"),
+                "this method",
+                Some("Images can only be uploaded with `POST`, `PUT` or `PATCH`"),
+            ));
+        }
+    };
+
+    let body = match sxcu.body.as_deref() {
+        None | Some("MultipartFormData") => RequestBody::Multipart,
+        Some("Binary") => RequestBody::Binary,
+        Some(body) => {
+            return Err(source.error(
+                source.span_of_value(body),
+                format!("Unsupported body type `{body}`"),
+                "this body type",
+                Some("Only `MultipartFormData` and `Binary` are supported"),
+            ));
+        }
+    };
+
+    let pairs = |map: BTreeMap<String, String>| -> Result<Vec<Pair>, SxcuError> {
+        map.into_iter()
+            .map(|(name, value)| {
+                Ok(Pair {
+                    value: static_value(source, &value)?,
+                    name,
+                })
+            })
+            .collect()
+    };
+
+    Ok(UploadProvider {
+        name: name.or(sxcu.name).unwrap_or(default_name),
+        url: static_value(source, &sxcu.request_url)?,
+        method,
+        field: sxcu.file_form_name.unwrap_or_else(|| String::from("file")),
+        body,
+        query: pairs(sxcu.parameters)?,
+        headers: pairs(sxcu.headers)?,
+        form: pairs(sxcu.arguments)?,
+        response: Response {
+            link: Some(
+                sxcu.url
+                    .as_deref()
+                    .map_or(Ok(String::from("{response}")), |url| {
+                        convert_template(source, url)
+                    })?,
+            ),
+            deletion_link: sxcu
+                .deletion_url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .map(|url| convert_template(source, url))
+                .transpose()?,
+            ..Response::default()
+        },
+    })
+}
+
+/// Find the `}` which closes the placeholder starting at the `{` at `start`.
+///
+/// Balanced and escaped braces inside of the placeholder are skipped
+fn placeholder_end(value: &str, start: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut chars = value[start + 1..].char_indices();
+
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(start + 1 + i),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Syntax of ShareX versions older than 13.7, which we don't support
+fn check_old_syntax(source: &Source, value: &str) -> Result<(), SxcuError> {
+    ["$json:", "$regex:", "$xml:", "$response$"]
+        .iter()
+        .find_map(|old| value.find(old).map(|offset| (offset, old.len())))
+        .map_or(Ok(()), |(offset, len)| {
+            Err(source.error(
+                source.span_of(value, offset, len),
+                "Syntax of ShareX versions older than 13.7 is not supported",
+                "old syntax",
+                Some(
+                    "Open the custom uploader in a recent version of ShareX, then export it again",
+                ),
+            ))
+        })
+}
+
+/// A value which must not contain any placeholders
+fn static_value(source: &Source, value: &str) -> Result<String, SxcuError> {
+    check_old_syntax(source, value)?;
+
+    let mut output = String::new();
+    let mut chars = value.char_indices().peekable();
+
+    while let Some((offset, ch)) = chars.next() {
+        match ch {
+            '\\' if chars
+                .peek()
+                .is_some_and(|&(_, next)| matches!(next, '{' | '}' | '\\')) =>
+            {
+                output.extend(chars.next().map(|(_, ch)| ch));
+            }
+            '{' => {
+                let len = placeholder_end(value, offset).map_or(1, |end| end + 1 - offset);
+                return Err(source.error(
+                    source.span_of(value, offset, len),
+                    "Placeholders are only supported in the `URL` and `DeletionURL`",
+                    "this placeholder",
+                    Some("Replace the placeholder with a fixed value"),
+                ));
+            }
+            ch => output.push(ch),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Convert a path such as `data.files[0].url` into a JSON pointer such as `/data/files/0/url`
+fn json_path_to_pointer(path: &str) -> String {
+    path.trim_start_matches('$')
+        .split(['.', '[', ']'])
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Convert the ShareX `URL` or `DeletionURL` into a `LinkTemplate`
+fn convert_template(source: &Source, value: &str) -> Result<String, SxcuError> {
+    check_old_syntax(source, value)?;
+
+    let mut template = String::new();
+    let mut chars = value.char_indices().peekable();
+
+    while let Some((offset, ch)) = chars.next() {
+        match ch {
+            '\\' if chars
+                .peek()
+                .is_some_and(|&(_, next)| matches!(next, '{' | '}')) =>
+            {
+                template.push('\\');
+                template.extend(chars.next().map(|(_, ch)| ch));
+            }
+            '{' => {
+                let Some(end) = placeholder_end(value, offset) else {
+                    return Err(source.error(
+                        source.span_of(value, offset, 1),
+                        "Unclosed `{`",
+                        "this brace",
+                        Some("Use `\\{` for a literal brace"),
+                    ));
+                };
+                // skip to after the placeholder
+                while chars.next_if(|&(i, _)| i <= end).is_some() {}
+
+                let placeholder = &value[offset + 1..end];
+                let converted = match placeholder.split_once(':') {
+                    None if placeholder == "response" => String::from("{response}"),
+                    Some(("json", path)) => format!("{{json:{}}}", json_path_to_pointer(path)),
+                    Some(("regex", _)) => format!("{{{placeholder}}}"),
+                    _ => {
+                        return Err(source.error(
+                            source.span_of(value, offset, end + 1 - offset),
+                            format!("Unsupported placeholder `{{{placeholder}}}`"),
+                            "this placeholder",
+                            Some(SUPPORTED_PLACEHOLDERS),
+                        ));
+                    }
+                };
+
+                if let Err(err) = LinkTemplate::parse(&converted) {
+                    return Err(source.error(
+                        source.span_of(value, offset, end + 1 - offset),
+                        format!("Invalid placeholder: {err}"),
+                        "this placeholder",
+                        None,
+                    ));
+                }
+
+                template.push_str(&converted);
+            }
+            ch => template.push(ch),
+        }
+    }
+
+    Ok(template)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Parse the `.sxcu` file contents
+    fn parse_sxcu(text: &str) -> Result<UploadProvider, SxcuError> {
+        parse(
+            &Source {
+                name: String::from("test.sxcu"),
+                text,
+            },
+            None,
+            String::from("test"),
+        )
+    }
+
+    #[test]
+    fn full_uploader() {
+        let provider = parse_sxcu(
+            r#"{
+              "Version": "15.0.0",
+              "Name": "Example",
+              "DestinationType": "ImageUploader, FileUploader",
+              "RequestMethod": "PUT",
+              "RequestURL": "https://example.com/upload",
+              "Parameters": { "key": "1234" },
+              "Headers": { "Authorization": "Bearer abcd" },
+              "Body": "MultipartFormData",
+              "Arguments": { "expires": "24" },
+              "FileFormName": "image",
+              "URL": "https://i.example.com/{json:data.files[0].id}.png",
+              "DeletionURL": "{json:data.delete}"
+            }"#,
+        )
+        .expect("valid uploader");
+
+        let pair = |name: &str, value: &str| Pair {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+
+        assert_eq!(
+            provider,
+            UploadProvider {
+                name: String::from("Example"),
+                url: String::from("https://example.com/upload"),
+                method: HttpMethod::Put,
+                field: String::from("image"),
+                body: RequestBody::Multipart,
+                query: vec![pair("key", "1234")],
+                headers: vec![pair("Authorization", "Bearer abcd")],
+                form: vec![pair("expires", "24")],
+                response: Response {
+                    link: Some(String::from(
+                        "https://i.example.com/{json:/data/files/0/id}.png"
+                    )),
+                    deletion_link: Some(String::from("{json:/data/delete}")),
+                    ..Response::default()
+                },
+            },
+            "every supported key is converted"
+        );
+    }
+
+    #[test]
+    fn minimal_uploader() {
+        let provider = parse_sxcu(r#"{ "RequestURL": "https://example.com", "Body": "Binary" }"#)
+            .expect("valid uploader");
+
+        assert_eq!(provider.name, "test", "name of the file is the default");
+        assert_eq!(provider.method, HttpMethod::Post, "default method");
+        assert_eq!(provider.body, RequestBody::Binary, "binary body");
+        assert_eq!(
+            provider.response.link.as_deref(),
+            Some("{response}"),
+            "the whole response is the link by default"
+        );
+    }
+
+    #[test]
+    fn unsupported_syntax() {
+        let text = r#"{ "RequestURL": "https://example.com", "Body": "JSON" }"#;
+        let err = parse_sxcu(text).expect_err("JSON body is not supported");
+        assert_eq!(
+            &text[err.span.offset()..err.span.offset() + err.span.len()],
+            "JSON",
+            "points at the body type"
+        );
+
+        let text = r#"{ "RequestURL": "https://example.com", "URL": "a{xml:/link}b" }"#;
+        let err = parse_sxcu(text).expect_err("xml is not supported");
+        assert_eq!(
+            &text[err.span.offset()..err.span.offset() + err.span.len()],
+            "{xml:/link}",
+            "points at the placeholder"
+        );
+
+        let text = r#"{ "RequestURL": "https://example.com/{filename}" }"#;
+        let err = parse_sxcu(text).expect_err("dynamic request url is not supported");
+        assert_eq!(
+            &text[err.span.offset()..err.span.offset() + err.span.len()],
+            "{filename}",
+            "points at the placeholder in the request URL"
+        );
+
+        let text = r#"{ "RequestURL": "https://example.com", "URL": "$json:link$" }"#;
+        assert!(parse_sxcu(text).is_err(), "old syntax is not supported");
+
+        let text = r#"{ "RequestURL": "#;
+        assert!(parse_sxcu(text).is_err(), "invalid JSON");
+    }
+}
@@ -0,0 +1,310 @@
+//! Templates which build the link to the uploaded image out of the response
+//!
+//! Text is copied as-is, and these placeholders are replaced:
+//!
+//! - `{response}`: The whole response
+//! - `{json:/data/link}`: The string at the JSON pointer, see <https://datatracker.ietf.org/doc/html/rfc6901>
+//! - `{regex:href='([^']+)'}`: The first capture group of the regex, or the whole match if
+//!   there are no capture groups. Pick a capture group with `{regex:pattern|2}`
+//!
+//! Use `\{` and `\}` for literal braces. Braces inside of placeholders are allowed, as long as
+//! they are balanced, e.g. `{regex:[0-9]{4}}`.
+
+use super::UploadError;
+
+/// Could not parse the template
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum TemplateError {
+    /// A `{` does not have a matching `}`
+    #[error("Unclosed `{{`")]
+    Unclosed {
+        /// Byte offset of the `{`
+        offset: usize,
+    },
+    /// The placeholder does not exist
+    #[error("Unknown placeholder `{{{name}}}`")]
+    UnknownPlaceholder {
+        /// Name of the placeholder
+        name: String,
+        /// Byte offset of the `{`
+        offset: usize,
+        /// Length of the placeholder in bytes, including the braces
+        len: usize,
+    },
+    /// The regex in `{regex:...}` is invalid
+    #[error("Invalid regex: {err}")]
+    InvalidRegex {
+        /// Why the regex is invalid
+        err: regex::Error,
+        /// Byte offset of the `{`
+        offset: usize,
+        /// Length of the placeholder in bytes, including the braces
+        len: usize,
+    },
+}
+
+impl TemplateError {
+    /// Byte offset and length of the part of the template which is invalid
+    pub const fn span(&self) -> (usize, usize) {
+        match *self {
+            Self::Unclosed { offset } => (offset, 1),
+            Self::UnknownPlaceholder { offset, len, .. }
+            | Self::InvalidRegex { offset, len, .. } => (offset, len),
+        }
+    }
+}
+
+/// Which part of the regex match to use
+#[derive(Debug, Clone)]
+enum Group {
+    /// The first capture group, or the whole match if there are no capture groups
+    FirstOrWhole,
+    /// The capture group with this index. `0` is the whole match
+    Index(usize),
+}
+
+/// A part of the template
+#[derive(Debug, Clone)]
+enum Segment {
+    /// Copied as-is
+    Text(String),
+    /// The whole response
+    Response,
+    /// String at this JSON pointer
+    Json(String),
+    /// Capture group of the regex
+    Regex(regex::Regex, Group),
+}
+
+/// A parsed template
+#[derive(Debug, Clone)]
+pub struct LinkTemplate(Vec<Segment>);
+
+impl LinkTemplate {
+    /// The whole response
+    pub fn response() -> Self {
+        Self(vec![Segment::Response])
+    }
+
+    /// String at the JSON pointer
+    pub fn json_pointer(pointer: &str) -> Self {
+        Self(vec![Segment::Json(pointer.to_string())])
+    }
+
+    /// The first capture group of the regex, or the whole match
+    pub fn regex(regex: &str) -> Result<Self, regex::Error> {
+        Ok(Self(vec![Segment::Regex(
+            regex::Regex::new(regex)?,
+            Group::FirstOrWhole,
+        )]))
+    }
+
+    /// Parse the template
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((offset, ch)) = chars.next() {
+            match ch {
+                '\\' if chars
+                    .peek()
+                    .is_some_and(|&(_, next)| next == '{' || next == '}') =>
+                {
+                    text.extend(chars.next().map(|(_, ch)| ch));
+                }
+                '{' => {
+                    // find the matching `}`, skipping over balanced and escaped braces
+                    let mut depth = 0_usize;
+                    let mut end = None;
+                    while let Some((i, ch)) = chars.next() {
+                        match ch {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '{' => depth += 1,
+                            '}' if depth == 0 => {
+                                end = Some(i);
+                                break;
+                            }
+                            '}' => depth -= 1,
+                            _ => (),
+                        }
+                    }
+                    let end = end.ok_or(TemplateError::Unclosed { offset })?;
+
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Self::parse_placeholder(
+                        &template[offset + 1..end],
+                        offset,
+                        end + 1 - offset,
+                    )?);
+                }
+                ch => text.push(ch),
+            }
+        }
+
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(Self(segments))
+    }
+
+    /// Parse the contents of a placeholder, which is `len` bytes long and begins at `offset`
+    fn parse_placeholder(
+        placeholder: &str,
+        offset: usize,
+        len: usize,
+    ) -> Result<Segment, TemplateError> {
+        let (name, argument) = placeholder
+            .split_once(':')
+            .map_or((placeholder, None), |(name, arg)| (name, Some(arg)));
+
+        match (name, argument) {
+            ("response", None) => Ok(Segment::Response),
+            ("json", Some(pointer)) => Ok(Segment::Json(pointer.to_string())),
+            ("regex", Some(regex)) => {
+                // `pattern|2` picks a capture group. Otherwise, `|` is an alternation
+                let (regex, group) = regex
+                    .rsplit_once('|')
+                    .and_then(|(pattern, group)| Some((pattern, group.parse().ok()?)))
+                    .map_or((regex, Group::FirstOrWhole), |(pattern, group)| {
+                        (pattern, Group::Index(group))
+                    });
+
+                regex::Regex::new(regex)
+                    .map(|regex| Segment::Regex(regex, group))
+                    .map_err(|err| TemplateError::InvalidRegex { err, offset, len })
+            }
+            _ => Err(TemplateError::UnknownPlaceholder {
+                name: placeholder.to_string(),
+                offset,
+                len,
+            }),
+        }
+    }
+
+    /// Build the link out of the response
+    pub fn render(&self, response: &str) -> Result<String, UploadError> {
+        // only parse the JSON once, and only if needed
+        let json = self
+            .0
+            .iter()
+            .any(|segment| matches!(segment, Segment::Json(_)))
+            .then(|| serde_json::from_str::<serde_json::Value>(response))
+            .transpose()?;
+        let mut link = String::new();
+
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => link.push_str(text),
+                Segment::Response => link.push_str(response.trim()),
+                Segment::Json(pointer) => {
+                    link.push_str(
+                        json.as_ref()
+                            .and_then(|json| json.pointer(pointer))
+                            .and_then(serde_json::Value::as_str)
+                            .ok_or_else(|| UploadError::JsonPointer(pointer.clone()))?,
+                    );
+                }
+                Segment::Regex(regex, group) => {
+                    let captures = regex
+                        .captures(response)
+                        .ok_or_else(|| UploadError::NoMatch(regex.to_string()))?;
+                    let capture = match group {
+                        Group::FirstOrWhole => captures.get(1).or_else(|| captures.get(0)),
+                        Group::Index(index) => captures.get(*index),
+                    }
+                    .ok_or_else(|| UploadError::NoMatch(regex.to_string()))?;
+                    link.push_str(capture.as_str());
+                }
+            }
+        }
+
+        // e.g. the link is followed by a newline
+        let link = link.trim();
+
+        if link.is_empty() {
+            return Err(UploadError::EmptyLink);
+        }
+
+        Ok(link.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Render the template with the response
+    fn render(template: &str, response: &str) -> Option<String> {
+        LinkTemplate::parse(template)
+            .expect("valid template")
+            .render(response)
+            .ok()
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            render("{response}", "https://example.com/a.png\n").as_deref(),
+            Some("https://example.com/a.png"),
+            "whole response, trimmed"
+        );
+        assert_eq!(
+            render(
+                "https://i.example.com/{json:/data/id}.png",
+                r#"{"data": {"id": "abc"}}"#
+            )
+            .as_deref(),
+            Some("https://i.example.com/abc.png"),
+            "text around a JSON pointer"
+        );
+        assert_eq!(
+            render("{regex:id=([0-9]{3})}", "id=123").as_deref(),
+            Some("123"),
+            "first capture group, with braces inside of the regex"
+        );
+        assert_eq!(
+            render("{regex:(a)(b)|2}", "ab").as_deref(),
+            Some("b"),
+            "specific capture group"
+        );
+        assert_eq!(
+            render("{regex:x|ab}", "ab").as_deref(),
+            Some("ab"),
+            "alternation is not a capture group"
+        );
+        assert_eq!(
+            render(r"\{{response}\}", "a").as_deref(),
+            Some("{a}"),
+            "escaped braces"
+        );
+    }
+
+    #[test]
+    fn invalid_templates() {
+        assert!(
+            matches!(
+                LinkTemplate::parse("abc{json:/a"),
+                Err(TemplateError::Unclosed { offset: 3 })
+            ),
+            "unclosed placeholder"
+        );
+        assert_eq!(
+            LinkTemplate::parse("a{xml:/a}").err().map(|err| err.span()),
+            Some((1, 8)),
+            "span of unknown placeholder"
+        );
+        assert!(
+            matches!(
+                LinkTemplate::parse("{regex:(}"),
+                Err(TemplateError::InvalidRegex { .. })
+            ),
+            "invalid regex"
+        );
+    }
+}
@@ -1,20 +1,33 @@
 //! The ferrishot app
 
-/// A single client for HTTP requests
-static CLIENT: std::sync::LazyLock<reqwest::Client> =
-    std::sync::LazyLock::new(reqwest::Client::new);
-
+mod auto_save;
+mod capture_history;
 mod clipboard;
 mod config;
+mod delay;
+mod headless;
+mod http;
 mod icons;
+mod image_format;
 mod image_upload;
 mod message;
 mod rect;
+mod report;
 mod screenshot;
+mod upload_history;
+mod util;
 mod widget;
 
 #[cfg(target_os = "linux")]
 pub use clipboard::{CLIPBOARD_DAEMON_ID, run_clipboard_daemon};
 
-pub use config::{CLI, CONFIG, Config, DEFAULT_KDL_CONFIG_STR};
-pub use widget::{App, SAVED_IMAGE};
+pub use capture_history::finish_recording;
+pub use config::{CLI, CONFIG, Command, Config, DEFAULT_KDL_CONFIG_STR, check_config};
+pub use delay::wait;
+pub use headless::{crop_region, read_image, write_image};
+pub use report::{Action, Report, Status};
+pub use screenshot::{
+    CaptureBackend, FakeBackend, MonitorInfo, Screenshot, ScreenshotError, XcapBackend, screenshot,
+};
+pub use upload_history::run_uploads_command;
+pub use widget::{App, HistoryBrowser, SAVED_IMAGE};
@@ -1,20 +1,18 @@
 //! The ferrishot app
 
 use miette::miette;
-use std::sync::LazyLock;
+use std::{process::ExitCode, sync::LazyLock};
 
-use ferrishot::{App, CLI};
+use ferrishot::{Action, App, CLI, Report, Status};
 use iced::Font;
 use miette::IntoDiagnostic;
 
 /// RGBA bytes for the Logo of ferrishot. Generated with `build.rs`
 const LOGO: &[u8; 64 * 64 * 4] = include_bytes!(concat!(env!("OUT_DIR"), "/logo.bin"));
 
-fn main() -> miette::Result<()> {
+fn main() -> miette::Result<ExitCode> {
     // On linux, a daemon is required to provide clipboard access even when
-    // the process dies.
-    //
-    // More info: <https://docs.rs/arboard/3.5.0/arboard/trait.SetExtLinux.html#tymethod.wait>
+    // the process dies, as the clipboard is served by the process which owns it
     #[cfg(target_os = "linux")]
     {
         if std::env::args()
@@ -22,8 +20,13 @@ fn main() -> miette::Result<()> {
             .as_deref()
             .is_some_and(|arg| arg == ferrishot::CLIPBOARD_DAEMON_ID)
         {
-            ferrishot::run_clipboard_daemon().expect("Failed to run clipboard daemon");
-            return Ok(());
+            return Ok(match ferrishot::run_clipboard_daemon() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("Clipboard daemon failed: {err}");
+                    ExitCode::FAILURE
+                }
+            });
         }
     }
 
@@ -55,8 +58,18 @@ fn main() -> miette::Result<()> {
     // - add ways to increase logging with command line arguments. Currently you must use `RUST_LOG=info`
     // env_logger::builder().init();
 
+    if CLI.check_config {
+        ferrishot::check_config(&CLI.config_file)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
     LazyLock::force(&ferrishot::CONFIG);
 
+    if CLI.print_keymap {
+        print!("{}", ferrishot::CONFIG.keys);
+        return Ok(ExitCode::SUCCESS);
+    }
+
     if CLI.dump_default_config {
         std::fs::create_dir_all(
             std::path::PathBuf::from(&CLI.config_file)
@@ -67,37 +80,163 @@ fn main() -> miette::Result<()> {
         std::fs::write(&CLI.config_file, ferrishot::DEFAULT_KDL_CONFIG_STR).into_diagnostic()?;
         println!("Success");
 
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
-    iced::application(App::default, App::update, App::view)
+    if let Some(command) = &CLI.command {
+        match command {
+            ferrishot::Command::Uploads { command } => ferrishot::run_uploads_command(command)?,
+        }
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if CLI.history {
+        iced::application(
+            ferrishot::HistoryBrowser::new,
+            ferrishot::HistoryBrowser::update,
+            ferrishot::HistoryBrowser::view,
+        )
         .window(iced::window::Settings {
+            icon: Some(
+                iced::window::icon::from_rgba(LOGO.to_vec(), 64, 64)
+                    .expect("Icon to be valid RGBA bytes"),
+            ),
+            ..Default::default()
+        })
+        .title("ferrishot history")
+        .default_font(Font::MONOSPACE)
+        .run()
+        .map_err(|err| miette!("Failed to open the history: {err}"))?;
+
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let input = CLI.input();
+
+    let screenshot = if let Some(input) = input {
+        ferrishot::read_image(input)
+    } else {
+        ferrishot::wait(
+            ferrishot::CONFIG.delay,
+            ferrishot::CONFIG.delay_notification,
+        );
+
+        ferrishot::screenshot().map_err(|err| miette!("Failed to take a screenshot: {err}"))
+    };
+
+    let screenshot = match screenshot {
+        Ok(screenshot) => screenshot,
+        Err(err) => return Ok(finish(&Report::failure(Status::CaptureFailed, None, err))),
+    };
+
+    // capture a fixed region without ever opening the window
+    if let Some(region) = CLI.region {
+        let output = CLI
+            .output
+            .as_ref()
+            .expect("`--region` requires `--output`, as enforced by clap");
+
+        let report = match ferrishot::crop_region(&screenshot, region, CLI.monitor.as_deref()) {
+            Ok(image) => {
+                let report = Report::success(Action::Output, region);
+                match ferrishot::write_image(&image, output) {
+                    Ok(Some(path)) => report.with_path(path),
+                    Ok(None) => report,
+                    Err(err) => report.with_error(Status::SaveFailed, err),
+                }
+            }
+            Err(err) => Report::failure(Status::CaptureFailed, Some(Action::Output), err),
+        };
+
+        return Ok(finish(&report));
+    }
+
+    // With a single monitor, we can just go fullscreen. But a fullscreen window
+    // only covers 1 monitor, so with several monitors we instead create an
+    // undecorated window which spans the entire virtual desktop
+    //
+    // The position and size of the window are in logical pixels, while the screenshot is
+    // in physical pixels. They only match when the scale factor of every monitor is 1.0,
+    // which is the only one supported for now
+    let window_settings = if input.is_some() {
+        // the image we opened is most likely not the same size as the monitor,
+        // so the window has exactly the size of the image to keep the coordinates
+        // of the selection the same as the coordinates of the image. The size is in
+        // logical pixels, so with a scale factor other than 1.0 the image is shown scaled
+        iced::window::Settings {
+            level: iced::window::Level::Normal,
+            resizable: false,
+            size: iced::Size::new(screenshot.width() as f32, screenshot.height() as f32),
+            ..Default::default()
+        }
+    } else if screenshot.monitors().len() == 1 {
+        iced::window::Settings {
             level: iced::window::Level::Normal,
             fullscreen: true,
+            ..Default::default()
+        }
+    } else {
+        let (x, y) = screenshot.origin();
+        iced::window::Settings {
+            level: iced::window::Level::AlwaysOnTop,
+            decorations: false,
+            resizable: false,
+            position: iced::window::Position::Specific(iced::Point::new(x as f32, y as f32)),
+            size: iced::Size::new(screenshot.width() as f32, screenshot.height() as f32),
+            ..Default::default()
+        }
+    };
+
+    iced::application(move || App::new(screenshot.clone()), App::update, App::view)
+        .window(iced::window::Settings {
             icon: Some(
                 iced::window::icon::from_rgba(LOGO.to_vec(), 64, 64)
                     .expect("Icon to be valid RGBA bytes"),
             ),
-            ..Default::default()
+            ..window_settings
         })
         .title("ferrishot")
         .default_font(Font::MONOSPACE)
         .run()
         .map_err(|err| miette!("Failed to start ferrishot: {err}"))?;
 
-    // open file explorer to choose where to save the image
-    if let Some(saved_image) = ferrishot::SAVED_IMAGE.get() {
-        if let Some(save_path) = rfd::FileDialog::new()
-            .set_title("Save Screenshot")
-            .save_file()
-        {
-            saved_image
-                .save(save_path)
-                .map_err(|err| miette!("Failed to save the screenshot: {err}"))?;
+    let report = Report::last();
+
+    // the file dialog is opened by the app itself, so there is only something left
+    // to do if the image is written to the `--output`
+    let (Some(saved_image), Some(output)) = (ferrishot::SAVED_IMAGE.get(), &CLI.output) else {
+        return Ok(finish(&report));
+    };
+
+    let report = match ferrishot::write_image(saved_image, output) {
+        Ok(Some(path)) => report.with_path(path),
+        Ok(None) => report,
+        Err(err) => report.with_error(Status::SaveFailed, err),
+    };
+
+    Ok(finish(&report))
+}
+
+/// Wait for the capture history, print the errors and the `--print-result` summary,
+/// then exit with the exit code corresponding to the outcome
+fn finish(report: &Report) -> ExitCode {
+    ferrishot::finish_recording();
+
+    if let Some(error) = &report.error {
+        eprintln!("Error: {error}");
+    }
+
+    if let Some(format) = CLI.print_result {
+        let summary = report.render(format);
+
+        // don't mix the summary into the image
+        if CLI.writes_to_stdout() {
+            eprintln!("{summary}");
         } else {
-            log::info!("The file dialog was closed before a file was chosen");
+            println!("{summary}");
         }
     }
 
-    Ok(())
+    report.status.into()
 }
@@ -13,6 +13,8 @@ use crate::{
 pub enum Message {
     /// Click "close" on the image upload menu
     ExitImageUploadMenu,
+    /// Copy the link to the uploaded image to the clipboard
+    CopyUploadedUrl,
     /// The image was uploaded to the internet
     ImageUploaded {
         /// link to the uploaded image
@@ -20,6 +22,23 @@ pub enum Message {
     },
     /// An error occured, display to the user
     Error(String),
+    /// The file dialog opened by `SaveScreenshot` was closed
+    ScreenshotSaved {
+        /// Path the screenshot was saved to, `None` if no file was chosen, or why saving failed
+        saved: Result<Option<std::path::PathBuf>, String>,
+        /// The selection which was saved
+        rect: Rectangle,
+        /// The window, hidden while the file dialog is open
+        window: iced::window::Id,
+        /// Mode of the window before it was hidden
+        mode: iced::window::Mode,
+    },
+    /// Could not upload the image to the internet
+    UploadFailed(String),
+    /// Something happened while uploading the image
+    UploadEvent(crate::image_upload::UploadEvent),
+    /// Stop uploading the image
+    CancelUpload,
     /// Abort selecting a letter
     LettersAbort,
     /// A region was picked using `Letters` widget
@@ -87,16 +106,19 @@ pub enum Message {
         /// How fast the selection should move
         speed: Speed,
     },
-    /// Holding right-click, the selection will move the
-    /// nearest corner to the cursor
-    ResizeToCursor {
-        /// Current position of the cursor
-        cursor_pos: Point,
-        /// Current selection
-        selection: Selection,
-        /// A key to obtain `&mut Selection` from `Option<Selection>` with a guarantee that it will
-        /// always be there (to bypass the limitation that we cannot pass `&mut Selection` in a `Message`)
-        sel_is_some: SelectionIsSome,
+    /// A key or mouse button was pressed, or the scroll wheel moved. It may be part of a keybinding
+    InputPressed {
+        /// What was pressed. For keys, Shift is already applied, e.g. `G` instead of `g`
+        input: crate::config::Input,
+        /// Modifiers held while pressing it, except for Shift
+        modifiers: iced::keyboard::Modifiers,
+        /// Position of the cursor at the time
+        cursor: Option<Point>,
+    },
+    /// The `key-timeout` ran out after a key was pressed
+    KeyTimeout {
+        /// Which key press the timeout is for. If other keys were pressed since, it is ignored
+        key_press: u64,
     },
     /// An action can be triggered by a keybind
     ///
@@ -112,5 +134,8 @@ pub enum Message {
         /// It has an effect for stuff like moving the selection right by `N` pixels
         /// in which case we'd move to the right by `N * count` instead
         count: u32,
+        /// The key or mouse button which was just pressed to trigger it. `None` when it
+        /// was triggered some other way, e.g. after the `key-timeout` or by a button
+        input: Option<crate::config::Input>,
     },
 }
@@ -73,6 +73,7 @@ pub enum Side {
     strum::EnumString,
     strum::IntoStaticStr,
     strum::EnumIter,
+    strum::Display,
 )]
 #[strum(serialize_all = "kebab-case")]
 pub enum Direction {
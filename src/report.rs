@@ -0,0 +1,257 @@
+//! Tell scripts which wrap ferrishot what happened
+//!
+//! The outcome is communicated through the exit code, and optionally a summary
+//! printed with `--print-result json`.
+
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    sync::{Mutex, PoisonError},
+};
+
+use iced::Rectangle;
+use serde::Serialize;
+
+use crate::config::Region;
+
+/// The last thing that happened inside of the app.
+///
+/// Just like `SAVED_IMAGE`, this is how the app tells `main` what happened after the window closes.
+/// Unlike `SAVED_IMAGE`, it can be set many times: e.g. when an upload fails and the user
+/// tries again, the later outcome replaces the earlier one.
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+/// Format of the summary printed by `--print-result`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResultFormat {
+    /// A single line of JSON
+    Json,
+}
+
+/// Outcome of running ferrishot, which determines the exit code
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// The screenshot was copied, saved, uploaded or written to the output
+    Success,
+    /// The app was closed without doing anything with the screenshot
+    #[default]
+    Cancelled,
+    /// Could not capture the screen or read the input image
+    CaptureFailed,
+    /// Could not save or write the screenshot
+    SaveFailed,
+    /// Could not upload the screenshot
+    UploadFailed,
+    /// Could not copy the screenshot to the clipboard
+    CopyFailed,
+}
+
+impl Status {
+    /// The exit code which corresponds to this status.
+    ///
+    /// `1` is not used, because it is the exit code for any other error
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Cancelled => 2,
+            Self::CaptureFailed => 3,
+            Self::SaveFailed => 4,
+            Self::UploadFailed => 5,
+            Self::CopyFailed => 6,
+        }
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        Self::from(status.code())
+    }
+}
+
+/// What was done with the screenshot
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Copied to the clipboard
+    Copy,
+    /// Saved to a file chosen in the file dialog
+    Save,
+    /// Saved into the `save-directory`
+    SaveToDirectory,
+    /// Uploaded to the internet
+    Upload,
+    /// Written to the `--output`
+    Output,
+}
+
+/// A rectangle of the screenshot, in pixels
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    /// x-coordinate of the top-left corner
+    pub x: u32,
+    /// y-coordinate of the top-left corner
+    pub y: u32,
+    /// Width of the rectangle
+    pub width: u32,
+    /// Height of the rectangle
+    pub height: u32,
+}
+
+impl From<Rectangle> for Rect {
+    fn from(rect: Rectangle) -> Self {
+        Self {
+            x: rect.x as u32,
+            y: rect.y as u32,
+            width: rect.width as u32,
+            height: rect.height as u32,
+        }
+    }
+}
+
+impl From<Region> for Rect {
+    fn from(region: Region) -> Self {
+        Self {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+        }
+    }
+}
+
+/// Summary of what happened
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// How it went
+    pub status: Status,
+    /// What was done with the screenshot. `None` if nothing was done with it
+    pub action: Option<Action>,
+    /// The selected area, relative to the top-left corner of the screenshot
+    pub selection: Option<Rect>,
+    /// Where the screenshot was saved
+    pub path: Option<PathBuf>,
+    /// Link to the uploaded screenshot
+    pub url: Option<String>,
+    /// What went wrong
+    pub error: Option<String>,
+}
+
+impl Report {
+    /// The `action` was successfully performed on the `selection`
+    pub fn success(action: Action, selection: impl Into<Rect>) -> Self {
+        Self {
+            status: Status::Success,
+            action: Some(action),
+            selection: Some(selection.into()),
+            ..Default::default()
+        }
+    }
+
+    /// The `action` failed
+    pub fn failure(status: Status, action: Option<Action>, error: impl ToString) -> Self {
+        Self {
+            status,
+            action,
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Set where the screenshot was saved
+    #[must_use]
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// The action turned out to fail after all
+    #[must_use]
+    pub fn with_error(mut self, status: Status, error: impl ToString) -> Self {
+        self.status = status;
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Set the link to the uploaded screenshot
+    #[must_use]
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    /// Remember this report, replacing the previous one
+    pub fn record(self) {
+        *REPORT.lock().unwrap_or_else(PoisonError::into_inner) = Some(self);
+    }
+
+    /// The last recorded report. If nothing was recorded, the user cancelled.
+    pub fn last() -> Self {
+        REPORT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// Render the report in the given format
+    pub fn render(&self, format: ResultFormat) -> String {
+        match format {
+            ResultFormat::Json => {
+                serde_json::to_string(self).expect("report only contains serializable values")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_json() {
+        let report = Report::success(
+            Action::SaveToDirectory,
+            Rectangle {
+                x: 10.0,
+                y: 20.0,
+                width: 300.0,
+                height: 400.0,
+            },
+        )
+        .with_path(PathBuf::from("/tmp/shot.png"));
+
+        assert_eq!(
+            report.render(ResultFormat::Json),
+            r#"{"status":"success","action":"save-to-directory","selection":{"x":10,"y":20,"width":300,"height":400},"path":"/tmp/shot.png","url":null,"error":null}"#,
+            "successful save"
+        );
+        assert_eq!(
+            Report::default().render(ResultFormat::Json),
+            r#"{"status":"cancelled","action":null,"selection":null,"path":null,"url":null,"error":null}"#,
+            "cancelled"
+        );
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let statuses = [
+            Status::Success,
+            Status::Cancelled,
+            Status::CaptureFailed,
+            Status::SaveFailed,
+            Status::UploadFailed,
+            Status::CopyFailed,
+        ];
+
+        for (i, a) in statuses.iter().enumerate() {
+            for b in &statuses[i + 1..] {
+                assert_ne!(
+                    a.code(),
+                    b.code(),
+                    "{a:?} and {b:?} have the same exit code"
+                );
+            }
+        }
+    }
+}
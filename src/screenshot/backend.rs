@@ -0,0 +1,155 @@
+//! Backends which are able to capture the screen
+//!
+//! The `XcapBackend` is used to take real screenshots. The `FakeBackend` returns
+//! synthetic images, so that everything which comes after taking a screenshot
+//! (selecting, cropping...) can be tested on a machine without a display.
+
+use super::ScreenshotError;
+
+/// A monitor, as reported by the `CaptureBackend`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorInfo {
+    /// Human-readable name of the monitor, e.g. `HDMI-1`
+    pub name: String,
+    /// Whether this is the primary monitor
+    pub is_primary: bool,
+    /// x-coordinate of the top-left corner on the desktop
+    pub x: i32,
+    /// y-coordinate of the top-left corner on the desktop
+    pub y: i32,
+    /// Width of the monitor in pixels
+    pub width: u32,
+    /// Height of the monitor in pixels
+    pub height: u32,
+}
+
+impl MonitorInfo {
+    /// Whether the point on the desktop is on this monitor
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        let left = i64::from(self.x);
+        let top = i64::from(self.y);
+        (left..left + i64::from(self.width)).contains(&i64::from(x))
+            && (top..top + i64::from(self.height)).contains(&i64::from(y))
+    }
+}
+
+/// Something that is able to capture the screen
+pub trait CaptureBackend {
+    /// All of the monitors connected to the computer
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ScreenshotError>;
+
+    /// Position of the cursor on the desktop
+    fn cursor_position(&self) -> Result<(i32, i32), ScreenshotError>;
+
+    /// Capture the contents of the monitor
+    fn capture(&self, monitor: &MonitorInfo) -> Result<image::RgbaImage, ScreenshotError>;
+}
+
+/// Captures the screen using `xcap`, and obtains the position of the cursor with `mouse_position`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct XcapBackend;
+
+impl CaptureBackend for XcapBackend {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ScreenshotError> {
+        xcap::Monitor::all()
+            .map_err(|err| ScreenshotError::Monitor(err.into()))?
+            .into_iter()
+            .map(|monitor| {
+                (|| -> Result<MonitorInfo, xcap::XCapError> {
+                    if monitor
+                        .scale_factor()
+                        .is_ok_and(|scale| (scale - 1.0).abs() > f32::EPSILON)
+                    {
+                        log::warn!(
+                            "Monitor {} is scaled, which is not supported. The window will not line up with it",
+                            monitor.name()?
+                        );
+                    }
+
+                    Ok(MonitorInfo {
+                        name: monitor.name()?,
+                        is_primary: monitor.is_primary()?,
+                        x: monitor.x()?,
+                        y: monitor.y()?,
+                        width: monitor.width()?,
+                        height: monitor.height()?,
+                    })
+                })()
+                .map_err(|err| ScreenshotError::Monitor(err.into()))
+            })
+            .collect()
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), ScreenshotError> {
+        match mouse_position::mouse_position::Mouse::get_mouse_position() {
+            mouse_position::mouse_position::Mouse::Position { x, y } => Ok((x, y)),
+            mouse_position::mouse_position::Mouse::Error => Err(ScreenshotError::MousePosition),
+        }
+    }
+
+    fn capture(&self, monitor: &MonitorInfo) -> Result<image::RgbaImage, ScreenshotError> {
+        xcap::Monitor::from_point(monitor.x, monitor.y)
+            .map_err(|err| ScreenshotError::Monitor(err.into()))?
+            .capture_image()
+            .map_err(|err| ScreenshotError::Screenshot(err.into()))
+    }
+}
+
+/// A backend which does not need a display. Each monitor is "captured" as a synthetic image.
+///
+/// The pixel at desktop coordinates `(x, y)` has the color `[x % 256, y % 256, index, 255]`,
+/// where `index` is the index of the monitor in `monitors`. This makes it easy to check
+/// which part of the desktop ended up in a cropped image.
+#[derive(Debug, Clone, Default)]
+pub struct FakeBackend {
+    /// Monitors of the fake desktop
+    pub monitors: Vec<MonitorInfo>,
+    /// Position of the cursor on the fake desktop.
+    ///
+    /// `None` means that the position of the cursor is unavailable
+    pub cursor_position: Option<(i32, i32)>,
+}
+
+impl FakeBackend {
+    /// Color of the pixel at desktop coordinates `(x, y)` of the monitor at `index`
+    pub fn pixel(x: i64, y: i64, index: usize) -> image::Rgba<u8> {
+        image::Rgba([
+            x.rem_euclid(256) as u8,
+            y.rem_euclid(256) as u8,
+            index as u8,
+            255,
+        ])
+    }
+}
+
+impl CaptureBackend for FakeBackend {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ScreenshotError> {
+        Ok(self.monitors.clone())
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), ScreenshotError> {
+        self.cursor_position.ok_or(ScreenshotError::MousePosition)
+    }
+
+    fn capture(&self, monitor: &MonitorInfo) -> Result<image::RgbaImage, ScreenshotError> {
+        let index = self
+            .monitors
+            .iter()
+            .position(|m| m == monitor)
+            .ok_or_else(|| {
+                ScreenshotError::Monitor(format!("Unknown monitor {}", monitor.name).into())
+            })?;
+
+        Ok(image::RgbaImage::from_fn(
+            monitor.width,
+            monitor.height,
+            |x, y| {
+                Self::pixel(
+                    i64::from(monitor.x) + i64::from(x),
+                    i64::from(monitor.y) + i64::from(y),
+                    index,
+                )
+            },
+        ))
+    }
+}
@@ -0,0 +1,394 @@
+//! Take screenshot of the entire desktop and store it
+//! This is then set as the background of the created full-screen window
+//!
+//! Every monitor is captured, and the captures are stitched together into a
+//! single image of the "virtual desktop". Each monitor is placed in that image
+//! according to its offset, so a selection can freely cross monitor boundaries.
+//!
+//! The screen is captured through a `CaptureBackend`.
+
+mod backend;
+
+use iced::{Rectangle, advanced::image::Bytes, widget::image::Handle};
+
+pub use backend::{CaptureBackend, FakeBackend, MonitorInfo, XcapBackend};
+
+/// A single monitor, positioned inside of the stitched `Screenshot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    /// Human-readable name of the monitor, e.g. `HDMI-1`
+    pub name: String,
+    /// Whether this is the primary monitor
+    pub is_primary: bool,
+    /// x-coordinate of the top-left corner, relative to the top-left corner of the `Screenshot`
+    pub x: u32,
+    /// y-coordinate of the top-left corner, relative to the top-left corner of the `Screenshot`
+    pub y: u32,
+    /// Width of the monitor in pixels
+    pub width: u32,
+    /// Height of the monitor in pixels
+    pub height: u32,
+}
+
+impl Monitor {
+    /// Area of the `Screenshot` covered by this monitor
+    pub fn rect(&self) -> Rectangle {
+        Rectangle {
+            x: self.x as f32,
+            y: self.y as f32,
+            width: self.width as f32,
+            height: self.height as f32,
+        }
+    }
+}
+
+/// The `Screenshot` is a wrapper for a handle pointing to decoded image pixels in RGBA format.
+///
+/// This is a more specialized version of `iced::widget::image::Handle`
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    /// Pixels of the entire virtual desktop
+    handle: Handle,
+    /// Monitors which make up the virtual desktop
+    monitors: Vec<Monitor>,
+    /// Index into `monitors` of the monitor which the mouse was on, when the screenshot was taken
+    active_monitor: usize,
+    /// Position of the top-left corner of the virtual desktop, in desktop coordinates.
+    ///
+    /// Monitors to the left or above the primary monitor have negative coordinates
+    origin: (i32, i32),
+}
+
+impl Screenshot {
+    /// Use an existing image instead of capturing the screen.
+    ///
+    /// The image is treated as if it was a single monitor. Each pixel of the image becomes
+    /// one logical pixel of the window, so with a scale factor other than 1.0 it is shown scaled
+    pub fn from_image(image: image::RgbaImage) -> Self {
+        let (width, height) = image.dimensions();
+
+        Self {
+            handle: Handle::from_rgba(width, height, image.into_raw()),
+            monitors: vec![Monitor {
+                name: String::new(),
+                is_primary: true,
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+            active_monitor: 0,
+            origin: (0, 0),
+        }
+    }
+
+    /// Width of the image
+    pub fn width(&self) -> u32 {
+        self.raw().0
+    }
+
+    /// Height of the image
+    pub fn height(&self) -> u32 {
+        self.raw().1
+    }
+
+    /// RGBA bytes of the image
+    pub fn bytes(&self) -> &Bytes {
+        self.raw().2
+    }
+
+    /// Handle to the image, to be rendered
+    pub fn handle(&self) -> Handle {
+        self.handle.clone()
+    }
+
+    /// Monitors that make up this screenshot
+    pub fn monitors(&self) -> &[Monitor] {
+        &self.monitors
+    }
+
+    /// The monitor which the mouse was on when the screenshot was taken
+    pub fn active_monitor(&self) -> &Monitor {
+        &self.monitors[self.active_monitor]
+    }
+
+    /// The monitor which contains the point, falling back to the active monitor
+    pub fn monitor_at(&self, point: iced::Point) -> &Monitor {
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.rect().contains(point))
+            .unwrap_or_else(|| self.active_monitor())
+    }
+
+    /// Position of the top-left corner of the screenshot, in desktop coordinates
+    pub const fn origin(&self) -> (i32, i32) {
+        self.origin
+    }
+
+    /// Returns the width, height and RGBA pixels
+    fn raw(&self) -> (u32, u32, &Bytes) {
+        let Handle::Rgba {
+            width,
+            height,
+            ref pixels,
+            ..
+        } = self.handle
+        else {
+            unreachable!("handle is guaranteed to be Rgba")
+        };
+        (width, height, pixels)
+    }
+}
+
+impl From<Screenshot> for Handle {
+    fn from(value: Screenshot) -> Self {
+        value.handle
+    }
+}
+
+/// Error which can be converted from any error type of a `CaptureBackend`
+pub type BackendError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Could not retrieve the screenshot
+#[derive(thiserror::Error, Debug)]
+pub enum ScreenshotError {
+    /// The position of the mouse is unavailable
+    #[error("Could not get position of the mouse")]
+    MousePosition,
+    #[error("Could not get the monitors: {0}")]
+    /// Could not list the monitors, or obtain information about one of them
+    Monitor(BackendError),
+    /// There are no monitors to take a screenshot of
+    #[error("There are no monitors to take a screenshot of")]
+    NoMonitors,
+    /// Could not capture the screenshot for some reason
+    #[error("Could not take a screenshot: {0}")]
+    Screenshot(BackendError),
+}
+
+/// Take a screenshot of every monitor using the default backend and return a handle to the stitched image
+pub fn screenshot() -> Result<Screenshot, ScreenshotError> {
+    Screenshot::capture(&XcapBackend)
+}
+
+impl Screenshot {
+    /// Take a screenshot of every monitor using the `backend`, and stitch them together
+    ///
+    /// The monitor which contains the cursor becomes the active one. If there is no such
+    /// monitor, the primary monitor is used instead.
+    pub fn capture(backend: &dyn CaptureBackend) -> Result<Self, ScreenshotError> {
+        let captures = backend
+            .monitors()?
+            .into_iter()
+            .map(|monitor| {
+                let image = backend.capture(&monitor)?;
+                Ok((monitor, image))
+            })
+            .collect::<Result<Vec<_>, ScreenshotError>>()?;
+
+        // the position of the mouse is only used to decide which monitor is "active",
+        // so it's fine if it is not available
+        let mouse_position = backend
+            .cursor_position()
+            .inspect_err(|err| log::warn!("{err}"))
+            .ok();
+
+        let active_monitor = captures
+            .iter()
+            .position(|(monitor, _)| mouse_position.is_some_and(|pos| monitor.contains(pos)))
+            .or_else(|| captures.iter().position(|(monitor, _)| monitor.is_primary))
+            .unwrap_or_default();
+
+        stitch(captures, active_monitor)
+    }
+}
+
+/// Combine captures of each monitor into a single image of the virtual desktop.
+///
+/// Areas of the virtual desktop not covered by any monitor (e.g. when monitors
+/// have different resolutions) are transparent.
+///
+/// The monitors are placed at their position on the desktop, which is only the same as
+/// their position in physical pixels if their scale factor is 1.0. Other scale factors
+/// are not supported.
+fn stitch(
+    captures: Vec<(MonitorInfo, image::RgbaImage)>,
+    active_monitor: usize,
+) -> Result<Screenshot, ScreenshotError> {
+    let origin_x = captures
+        .iter()
+        .map(|(monitor, _)| monitor.x)
+        .min()
+        .ok_or(ScreenshotError::NoMonitors)?;
+    let origin_y = captures
+        .iter()
+        .map(|(monitor, _)| monitor.y)
+        .min()
+        .ok_or(ScreenshotError::NoMonitors)?;
+
+    let monitors = captures
+        .iter()
+        .map(|(monitor, image)| Monitor {
+            name: monitor.name.clone(),
+            is_primary: monitor.is_primary,
+            x: monitor.x.abs_diff(origin_x),
+            y: monitor.y.abs_diff(origin_y),
+            width: image.width(),
+            height: image.height(),
+        })
+        .collect::<Vec<_>>();
+
+    let width = monitors
+        .iter()
+        .map(|monitor| monitor.x + monitor.width)
+        .max()
+        .unwrap_or_default();
+    let height = monitors
+        .iter()
+        .map(|monitor| monitor.y + monitor.height)
+        .max()
+        .unwrap_or_default();
+
+    let mut desktop = image::RgbaImage::new(width, height);
+    for ((_, image), monitor) in captures.iter().zip(&monitors) {
+        image::imageops::replace(
+            &mut desktop,
+            image,
+            i64::from(monitor.x),
+            i64::from(monitor.y),
+        );
+    }
+
+    Ok(Screenshot {
+        handle: Handle::from_rgba(width, height, desktop.into_raw()),
+        monitors,
+        active_monitor,
+        origin: (origin_x, origin_y),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use iced::{Point, Size};
+
+    use super::*;
+    use crate::widget::selection::Selection;
+
+    /// Create a monitor for the `FakeBackend`
+    fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorInfo {
+        MonitorInfo {
+            name: name.to_string(),
+            is_primary: name == "primary",
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Two monitors side by side. The one on the left is taller, and has a negative x-coordinate
+    fn backend(cursor_position: Option<(i32, i32)>) -> FakeBackend {
+        FakeBackend {
+            monitors: vec![
+                monitor("secondary", -100, 0, 100, 50),
+                monitor("primary", 0, 0, 80, 40),
+            ],
+            cursor_position,
+        }
+    }
+
+    /// Convert the screenshot into an image, so we can inspect its pixels
+    fn to_image(screenshot: &Screenshot) -> image::RgbaImage {
+        image::RgbaImage::from_raw(
+            screenshot.width(),
+            screenshot.height(),
+            screenshot.bytes().to_vec(),
+        )
+        .expect("screenshot contains a valid image")
+    }
+
+    #[test]
+    fn stitch_monitors() {
+        let screenshot =
+            Screenshot::capture(&backend(Some((-10, 10)))).expect("fake backend never fails");
+
+        assert_eq!(screenshot.width(), 180, "width of both monitors combined");
+        assert_eq!(screenshot.height(), 50, "height of the tallest monitor");
+        assert_eq!(screenshot.origin(), (-100, 0), "left-most monitor");
+        assert_eq!(
+            screenshot
+                .monitors()
+                .iter()
+                .map(|monitor| (monitor.x, monitor.y))
+                .collect::<Vec<_>>(),
+            [(0, 0), (100, 0)],
+            "monitors are positioned relative to the top-left corner of the screenshot"
+        );
+        assert_eq!(
+            screenshot.active_monitor().name,
+            "secondary",
+            "the cursor is on the secondary monitor"
+        );
+
+        let image = to_image(&screenshot);
+        assert_eq!(
+            *image.get_pixel(10, 10),
+            FakeBackend::pixel(-90, 10, 0),
+            "pixel on the secondary monitor"
+        );
+        assert_eq!(
+            *image.get_pixel(150, 10),
+            FakeBackend::pixel(50, 10, 1),
+            "pixel on the primary monitor"
+        );
+        assert_eq!(
+            *image.get_pixel(150, 45),
+            image::Rgba([0, 0, 0, 0]),
+            "area not covered by any monitor is transparent"
+        );
+    }
+
+    #[test]
+    fn active_monitor_is_primary_without_cursor() {
+        let screenshot = Screenshot::capture(&backend(None)).expect("fake backend never fails");
+
+        assert_eq!(
+            screenshot.active_monitor().name,
+            "primary",
+            "falls back to the primary monitor"
+        );
+    }
+
+    #[test]
+    fn no_monitors() {
+        assert!(
+            matches!(
+                Screenshot::capture(&FakeBackend::default()),
+                Err(ScreenshotError::NoMonitors)
+            ),
+            "cannot take a screenshot without monitors"
+        );
+    }
+
+    #[test]
+    fn crop_selection_across_monitors() {
+        let screenshot = Screenshot::capture(&backend(None)).expect("fake backend never fails");
+
+        let cropped = Selection::new(Point::new(90.0, 5.0))
+            .with_size(|_| Size::new(20.0, 10.0))
+            .process_image(screenshot.width(), screenshot.height(), screenshot.bytes())
+            .into_rgba8();
+
+        assert_eq!(cropped.dimensions(), (20, 10), "size of the selection");
+        assert_eq!(
+            *cropped.get_pixel(0, 0),
+            FakeBackend::pixel(-10, 5, 0),
+            "left part of the selection is on the secondary monitor"
+        );
+        assert_eq!(
+            *cropped.get_pixel(15, 9),
+            FakeBackend::pixel(5, 14, 1),
+            "right part of the selection is on the primary monitor"
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Append-only history of uploaded images, so that their links and deletion links
+//! are not lost once ferrishot exits
+//!
+//! The history is stored in `uploads.jsonl` in the data directory. Each line is a JSON record:
+//! either an upload, or the deletion of an earlier upload. Records are never modified or
+//! removed. Each upload has a short random ID, which deletions refer to, so skipping a
+//! corrupted line does not change which upload an ID belongs to.
+
+use std::{
+    hash::BuildHasher as _,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use etcetera::BaseStrategy as _;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CONFIG,
+    config::UploadsCommand,
+    image_upload::{UploadError, Uploaded},
+};
+
+/// Could not read or write the upload history
+#[derive(thiserror::Error, Debug)]
+pub enum HistoryError {
+    /// Could not determine the data directory
+    #[error("Could not determine the data directory: {0}")]
+    HomeDir(#[from] etcetera::HomeDirError),
+    /// Could not read or write the history file
+    #[error("Could not access the upload history: {0}")]
+    Io(#[from] std::io::Error),
+    /// Could not serialize a record
+    #[error("Could not write to the upload history: {0}")]
+    Json(#[from] serde_json::Error),
+    /// There is no upload with this ID
+    #[error("There is no upload with ID {0}")]
+    UnknownId(String),
+    /// The upload was already deleted
+    #[error("Upload {0} was already deleted")]
+    AlreadyDeleted(String),
+    /// The provider did not give us a way to delete the upload
+    #[error("Upload {0} has no deletion link")]
+    NoDeletionLink(String),
+    /// Could not delete the upload
+    #[error(transparent)]
+    Upload(#[from] UploadError),
+}
+
+/// An image that was uploaded
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Upload {
+    /// Identifies the upload, e.g. `3fa9c21b`
+    pub id: String,
+    /// When the image was uploaded, in RFC 3339 format
+    pub timestamp: String,
+    /// Name of the upload provider
+    pub provider: String,
+    /// Link to the uploaded image
+    pub url: String,
+    /// Link which deletes the image, if the provider returned one
+    pub deletion_url: Option<String>,
+    /// Width of the image
+    pub width: u32,
+    /// Height of the image
+    pub height: u32,
+    /// Where a copy of the image is stored on this computer, if anywhere
+    pub local_path: Option<PathBuf>,
+}
+
+impl Upload {
+    /// An upload of an image with this size, which happened just now
+    pub fn new(
+        provider: &str,
+        uploaded: &Uploaded,
+        (width, height): (u32, u32),
+        local_path: Option<PathBuf>,
+    ) -> Self {
+        let now = chrono::Local::now();
+
+        Self {
+            // `RandomState` is seeded randomly, which is random enough to tell uploads apart
+            id: format!("{:08x}", std::hash::RandomState::new().hash_one(now) as u32),
+            timestamp: now.to_rfc3339(),
+            provider: provider.to_string(),
+            url: uploaded.link.clone(),
+            deletion_url: uploaded.deletion_link.clone(),
+            width,
+            height,
+            local_path,
+        }
+    }
+
+    /// A line of `ferrishot uploads list`, separated by tabs
+    fn list_line(&self, deleted: bool) -> String {
+        format!(
+            "{}\t{}\t{}\t{}x{}\t{}\t{}{}",
+            self.id,
+            self.timestamp,
+            self.provider,
+            self.width,
+            self.height,
+            self.url,
+            self.local_path
+                .as_deref()
+                .map_or_else(|| String::from("-"), |path| path.display().to_string()),
+            if deleted { "\t(deleted)" } else { "" }
+        )
+    }
+}
+
+/// A line of the history file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "record", rename_all = "kebab-case")]
+pub enum Record {
+    /// An image was uploaded
+    Upload(Upload),
+    /// An uploaded image was deleted
+    Deletion {
+        /// ID of the upload
+        id: String,
+        /// When the image was deleted, in RFC 3339 format
+        timestamp: String,
+    },
+}
+
+/// An upload, together with everything that happened to it afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The upload
+    pub upload: Upload,
+    /// When the image was deleted, if it was
+    pub deleted: Option<String>,
+}
+
+/// Location of the upload history
+pub fn history_file() -> Result<PathBuf, HistoryError> {
+    Ok(etcetera::choose_base_strategy()?
+        .data_dir()
+        .join("ferrishot")
+        .join("uploads.jsonl"))
+}
+
+/// Add the record to the end of the history
+pub fn append(path: &Path, record: &Record) -> Result<(), HistoryError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+
+    // a single write, so records of concurrent ferrishot instances don't interleave
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+
+    Ok(())
+}
+
+/// Read all uploads in the history. If there is no history yet, there are no uploads
+pub fn read(path: &Path) -> Result<Vec<Entry>, HistoryError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = Vec::<Entry>::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // a corrupted line should not make the rest of the history inaccessible
+        match serde_json::from_str::<Record>(line) {
+            Ok(Record::Upload(upload)) => entries.push(Entry {
+                upload,
+                deleted: None,
+            }),
+            Ok(Record::Deletion { id, timestamp }) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.upload.id == id) {
+                    entry.deleted = Some(timestamp);
+                }
+            }
+            Err(err) => log::warn!(
+                "Skipping line {} of {}: {err}",
+                line_number + 1,
+                path.display()
+            ),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Add the upload to the history. Failing to do so is not worth bothering the user over
+/// an upload which succeeded, so it is only logged
+pub fn record_upload(upload: Upload) {
+    if let Err(err) = history_file().and_then(|path| append(&path, &Record::Upload(upload))) {
+        log::error!("Could not add the upload to the history: {err}");
+    }
+}
+
+/// Delete the upload through its provider, then record the deletion in the history
+async fn delete(path: &Path, id: &str) -> Result<Entry, HistoryError> {
+    let entry = read(path)?
+        .into_iter()
+        .find(|entry| entry.upload.id == id)
+        .ok_or_else(|| HistoryError::UnknownId(id.to_string()))?;
+
+    if entry.deleted.is_some() {
+        return Err(HistoryError::AlreadyDeleted(id.to_string()));
+    }
+
+    let deletion_url = entry
+        .upload
+        .deletion_url
+        .as_deref()
+        .ok_or_else(|| HistoryError::NoDeletionLink(id.to_string()))?;
+
+    CONFIG
+        .upload_provider(&entry.upload.provider)?
+        .delete(
+            crate::http::client().map_err(UploadError::from)?,
+            deletion_url,
+        )
+        .await?;
+
+    append(
+        path,
+        &Record::Deletion {
+            id: id.to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+        },
+    )?;
+
+    Ok(entry)
+}
+
+/// Run the `uploads` subcommand
+pub fn run_uploads_command(command: &UploadsCommand) -> miette::Result<()> {
+    let path = history_file().map_err(|err| miette::miette!("{err}"))?;
+
+    match command {
+        UploadsCommand::List => {
+            for Entry { upload, deleted } in read(&path).map_err(|err| miette::miette!("{err}"))? {
+                println!("{}", upload.list_line(deleted.is_some()));
+            }
+        }
+        UploadsCommand::Delete { id } => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|err| miette::miette!("Failed to start the async runtime: {err}"))?;
+
+            let entry = runtime
+                .block_on(delete(&path, id))
+                .map_err(|err| miette::miette!("{err}"))?;
+
+            println!("Deleted {}", entry.upload.url);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An upload with this ID and link
+    fn upload(id: &str, url: &str) -> Upload {
+        Upload {
+            id: id.to_string(),
+            timestamp: String::from("2025-04-16T13:10:52+00:00"),
+            provider: String::from("the-null-pointer"),
+            url: url.to_string(),
+            deletion_url: None,
+            width: 800,
+            height: 600,
+            local_path: None,
+        }
+    }
+
+    /// A deletion of the upload with this ID
+    fn deletion(id: &str) -> Record {
+        Record::Deletion {
+            id: id.to_string(),
+            timestamp: String::from("2025-04-17T00:00:00+00:00"),
+        }
+    }
+
+    #[test]
+    fn append_and_read() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let path = dir.path().join("nested").join("uploads.jsonl");
+
+        assert_eq!(
+            read(&path).expect("missing history is fine"),
+            [],
+            "no history yet"
+        );
+
+        append(&path, &Record::Upload(upload("aa", "https://0x0.st/a.png"))).expect("append");
+        append(&path, &Record::Upload(upload("bb", "https://0x0.st/b.png"))).expect("append");
+        append(&path, &deletion("aa")).expect("append");
+
+        assert_eq!(
+            read(&path).expect("read the history"),
+            [
+                Entry {
+                    upload: upload("aa", "https://0x0.st/a.png"),
+                    deleted: Some(String::from("2025-04-17T00:00:00+00:00")),
+                },
+                Entry {
+                    upload: upload("bb", "https://0x0.st/b.png"),
+                    deleted: None,
+                },
+            ],
+            "uploads are in order, and deletions are applied"
+        );
+    }
+
+    #[test]
+    fn skip_corrupted_lines() {
+        let dir = tempfile::TempDir::new().expect("create a temporary directory");
+        let path = dir.path().join("uploads.jsonl");
+
+        append(&path, &Record::Upload(upload("aa", "https://0x0.st/a.png"))).expect("append");
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(b"{\"record\": \"upl\n"))
+            .expect("corrupt the history");
+        append(&path, &Record::Upload(upload("cc", "https://0x0.st/c.png"))).expect("append");
+        append(&path, &deletion("cc")).expect("append");
+
+        assert_eq!(
+            read(&path)
+                .expect("read the history")
+                .into_iter()
+                .map(|entry| (entry.upload.id, entry.deleted.is_some()))
+                .collect::<Vec<_>>(),
+            [(String::from("aa"), false), (String::from("cc"), true)],
+            "the corrupted line is skipped, and the deletion applies to the upload with its ID"
+        );
+    }
+
+    #[test]
+    fn unique_ids() {
+        let uploaded = Uploaded {
+            link: String::from("https://0x0.st/a.png"),
+            deletion_link: None,
+        };
+        let first = Upload::new("the-null-pointer", &uploaded, (1, 1), None);
+        let second = Upload::new("the-null-pointer", &uploaded, (1, 1), None);
+
+        assert_eq!(first.id.len(), 8, "short enough to type");
+        assert_ne!(first.id, second.id, "every upload has its own ID");
+    }
+
+    #[test]
+    fn list_line() {
+        let stored = Upload {
+            local_path: Some(PathBuf::from("/captures/a.png")),
+            ..upload("3fa9c21b", "https://0x0.st/a.png")
+        };
+
+        assert_eq!(
+            stored.list_line(false),
+            "3fa9c21b\t2025-04-16T13:10:52+00:00\tthe-null-pointer\t800x600\thttps://0x0.st/a.png\t/captures/a.png",
+            "starts with the ID, which `uploads delete` takes"
+        );
+        assert_eq!(
+            upload("bb", "https://0x0.st/b.png").list_line(true),
+            "bb\t2025-04-16T13:10:52+00:00\tthe-null-pointer\t800x600\thttps://0x0.st/b.png\t-\t(deleted)",
+            "no local copy, and deleted"
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! Small helpers which are shared by several modules
+
+use std::path::PathBuf;
+
+/// Expand the leading `~` in the path to the home directory
+pub fn expand_home(path: &str) -> Result<PathBuf, etcetera::HomeDirError> {
+    Ok(match path.strip_prefix('~') {
+        Some(rest) => etcetera::home_dir()?.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    })
+}
@@ -1,8 +1,10 @@
 //! Main logic for the application, handling of events and mutation of the state
 
 use crate::CONFIG;
+use crate::config::CLI;
 use crate::config::KeyAction;
 use crate::config::Place;
+use crate::config::{Input, KeyMods, KeyPress, MouseInput};
 use crate::widget::PickCorner;
 use crate::widget::selection::Speed;
 use iced::Length;
@@ -16,8 +18,10 @@ use iced::{
 };
 
 use crate::message::Message;
+use crate::report::{Action, Report, Status};
 use crate::screenshot::Screenshot;
 // use crate::widget::selection::selection_lock::OptionalSelectionExt;
+use iced::futures::StreamExt as _;
 use iced::widget::Stack;
 use iced::{Point, Size, Task};
 
@@ -28,49 +32,28 @@ use crate::widget::selection::{Selection, SelectionStatus};
 use super::Errors;
 use super::selection::OptionalSelectionExt as _;
 
-/// The image to save to a file, chosen by the user in a file picker.
+/// The image to write to the `--output`.
 ///
 /// Unfortunately, there is simply no way to communicate something from
 /// the inside of an iced application to the outside: i.e. "Return" something
 /// from an iced program exiting. So we have to use a global variable for this.
 ///
 /// This global is mutated just *once* at the end of the application's lifetime,
-/// when the window closes.
+/// when the window closes. It is then accessed just *once* to write it to the `--output`.
 ///
-/// It is then accessed just *once* to open the file dialog and let the user pick
-/// where they want to save their image.
-///
-/// Yes, at the moment we want this when using Ctrl + S to save as file:
-/// 1. Close the application to save the file and generate the image we'll save
-/// 2. Open the file explorer, and save the image to the specified path
-///
-/// When the file explorer is spawned from the inside of an iced window, closing
-/// this window will then also close the file explorer. It means that we can't
-/// close the window and then spawn an explorer.
-///
-/// The other option is to have both windows open at the same time. But this
-/// would be really odd. First of all, we will need to un-fullscreen the App
-/// because the file explorer can spawn under the app.
-///
-/// This is going to be sub-optimal. Currently, we give off the illusion of
-/// drawing shapes and rectangles on top of the desktop. It is not immediately
-/// obvious that the app is just another window which is full-screen.
-/// Doing the above would break that illusion.
-///
-/// Ideally, we would be able to spawn a file explorer *above* the window without
-/// having to close this. But this seems to not be possible. Perhaps in the
-/// future there will be some kind of file explorer Iced widget that we
-/// can use instead of the native file explorer.
+/// Images saved with the file dialog don't go through here: the file dialog is opened
+/// while the app is still running, so that errors can still be shown to the user.
+/// The window is hidden while the dialog is open, because the dialog could spawn under it.
 pub static SAVED_IMAGE: std::sync::OnceLock<image::DynamicImage> = std::sync::OnceLock::new();
 
 /// Holds the state for ferrishot
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct App {
     /// How many selections were created throughout the
     /// lifetime of the App
     pub selections_created: usize,
-    /// The full screenshot of the monitor from which ferrishot was invoked
-    /// We then create a window spanning the entire monitor, with this
+    /// The full screenshot of every monitor, stitched together.
+    /// We then create a window spanning all of the monitors, with this
     /// screenshot as background, with a canvas rendered on top - giving the
     /// illusion that we are drawing shapes on top of the screen.
     pub image: Screenshot,
@@ -81,11 +64,38 @@ pub struct App {
     /// Shows a grid of letters on the screen, pressing 3 letters in a row
     /// allows accessing 25 * 25 * 25 = 15,625 different locations
     pub picking_corner: Option<PickCorner>,
-    /// A link to the uploaded image
-    pub uploaded_url: Option<String>,
+    /// The image uploaded to the internet, with its link
+    pub uploaded_image: Option<super::UploadedImage>,
+    /// The image which is currently being uploaded
+    pub uploading: Option<super::Uploading>,
+    /// Keys pressed so far which are the start of a key sequence, waiting for the next key
+    pub pending_keys: Vec<KeyPress>,
+    /// How many times to execute the next motion
+    pub motion_count: Option<u32>,
+    /// How many keys were pressed, to tell which key press a `KeyTimeout` is for
+    pub key_presses: u64,
+    /// Position of the cursor when the last key was pressed
+    pub cursor_position: Option<Point>,
 }
 
 impl App {
+    /// Create the app, with the given screenshot as the background
+    pub fn new(image: Screenshot) -> Self {
+        Self {
+            selections_created: 0,
+            image,
+            selection: None,
+            errors: Errors::default(),
+            picking_corner: None,
+            uploaded_image: None,
+            uploading: None,
+            pending_keys: Vec::new(),
+            motion_count: None,
+            key_presses: 0,
+            cursor_position: None,
+        }
+    }
+
     /// Create an empty selection at the current position
     pub fn create_selection_at(&mut self, create_selection_at: Point) {
         let mut selection = Selection::new(create_selection_at);
@@ -112,12 +122,180 @@ impl App {
         iced::window::get_latest().then(|id| iced::window::close(id.expect("window to exist")))
     }
 
+    /// Hide the window, then let the user choose where to save the image in the file dialog.
+    ///
+    /// The window covers the whole desktop, so the file dialog would open behind it otherwise.
+    /// It is shown again if saving fails, so that the error can be shown to the user
+    fn save_with_file_dialog(image: image::DynamicImage, rect: Rectangle) -> Task<Message> {
+        iced::window::get_latest().then(move |window| {
+            let window = window.expect("window to exist");
+            let image = image.clone();
+
+            iced::window::get_mode(window).then(move |mode| {
+                let image = image.clone();
+
+                iced::window::set_mode(window, iced::window::Mode::Hidden).chain(Task::perform(
+                    async move {
+                        let saved = crate::image_format::save_with_file_dialog(&image).await;
+                        if let Ok(Some(_)) = saved {
+                            crate::capture_history::record(image);
+                        }
+                        saved.map_err(|err| err.to_string())
+                    },
+                    move |saved| Message::ScreenshotSaved {
+                        saved,
+                        rect,
+                        window,
+                        mode,
+                    },
+                ))
+            })
+        })
+    }
+
+    /// Handle a key press, which may trigger keybindings or be the start of a key sequence
+    fn press_key(&mut self, key: Input, modifiers: iced::keyboard::Modifiers) -> Task<Message> {
+        let key = (key, KeyMods(modifiers));
+
+        // The key interrupts the sequence, so the pending keys are triggered on their own
+        // first, e.g. `g2` triggers `g`. The count typed before them applies to them.
+        let interrupted =
+            if self.pending_keys.is_empty() || CONFIG.keys.continues(&self.pending_keys, &key) {
+                None
+            } else {
+                CONFIG
+                    .keys
+                    .timeout(&mut self.pending_keys)
+                    .map(|action| self.trigger_keybind(action, None))
+            };
+
+        // pressing numbers will have an effect, e.g. `200j` will
+        // move the selection down by 200px. Not in the middle of a sequence though
+        let number_pressed = if let (Input::Key(iced::keyboard::Key::Character(ch)), _) = &key {
+            ch.parse::<u32>().ok()
+        } else {
+            None
+        };
+        if let Some(number_pressed) = number_pressed.filter(|_| self.pending_keys.is_empty()) {
+            self.motion_count = Some(
+                self.motion_count
+                    .map_or(number_pressed, |count| count * 10 + number_pressed),
+            );
+        }
+
+        self.key_presses += 1;
+        let input = key.0.clone();
+        let actions = CONFIG.keys.press(&mut self.pending_keys, key);
+
+        // stop waiting for the rest of the sequence after a while
+        let timeout = if self.pending_keys.is_empty() {
+            Task::none()
+        } else {
+            let key_press = self.key_presses;
+            Task::perform(
+                tokio::time::sleep(std::time::Duration::from_millis(CONFIG.key_timeout.into())),
+                move |()| Message::KeyTimeout { key_press },
+            )
+        };
+
+        Task::batch(
+            interrupted
+                .into_iter()
+                .chain(
+                    actions
+                        .into_iter()
+                        .map(|action| self.trigger_keybind(action, Some(input.clone()))),
+                )
+                .chain(std::iter::once(timeout)),
+        )
+    }
+
+    /// Execute the action of a keybinding, as many times as the number typed before it
+    fn trigger_keybind(&mut self, action: KeyAction, input: Option<Input>) -> Task<Message> {
+        Task::done(Message::KeyBind {
+            action,
+            count: self.motion_count.take().unwrap_or(1),
+            input,
+        })
+    }
+
+    /// Copy a snippet of text made from the selected part of the screenshot, then exit
+    fn copy_snippet(
+        &mut self,
+        make_snippet: impl FnOnce(&Self, &image::DynamicImage, Selection) -> Result<String, String>,
+    ) -> Task<Message> {
+        let Some(selection) = self.selection.map(Selection::norm) else {
+            self.errors.push("There is no selection to copy");
+            return Task::none();
+        };
+
+        let cropped_image =
+            selection.process_image(self.image.width(), self.image.height(), self.image.bytes());
+
+        let copied = make_snippet(self, &cropped_image, selection)
+            .map_err(|err| (Status::SaveFailed, err))
+            .and_then(|snippet| {
+                crate::clipboard::set_text(&snippet).map_err(|err| {
+                    (
+                        Status::CopyFailed,
+                        format!("Could not copy the snippet: {err}"),
+                    )
+                })
+            });
+
+        match copied {
+            Ok(()) => {
+                // just a decoration, so it's ok if we fail to show the notification
+                let _ = notify_rust::Notification::new()
+                    .summary("Copied snippet to clipboard")
+                    .show();
+
+                crate::capture_history::record(cropped_image.clone());
+                Report::success(Action::Copy, selection.rect).record();
+
+                Self::exit()
+            }
+            Err((status, err)) => {
+                Report::failure(status, Some(Action::Copy), &err).record();
+                self.errors.push(err);
+                Task::none()
+            }
+        }
+    }
+
+    /// What snippets link to: the uploaded image, or else the image saved into the `save-directory`.
+    /// The link is only used if the selection did not change since it was uploaded
+    fn snippet_source(
+        &self,
+        cropped_image: &image::DynamicImage,
+        selection: Selection,
+    ) -> Result<String, String> {
+        if let Some(uploaded_image) = self
+            .uploaded_image
+            .as_ref()
+            .filter(|uploaded_image| uploaded_image.rect == selection.rect)
+        {
+            return Ok(uploaded_image.url.clone());
+        }
+
+        crate::auto_save::save_to_directory(
+            cropped_image,
+            &CONFIG.save_directory,
+            &CONFIG.filename_template,
+            &self.image.monitor_at(selection.rect.center()).name,
+            CONFIG.format,
+            &CONFIG.encode_options(),
+        )
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|err| format!("Could not save the screenshot: {err}"))
+    }
+
     /// Renders the app
     pub fn view(&self) -> iced::Element<Message> {
         Stack::new()
             // taken screenshot in the background
             .push(super::BackgroundImage {
-                image_handle: Screenshot::clone(&self.image).into(),
+                image_handle: self.image.handle(),
             })
             // event handler + shade in the background if no selection
             .push(Canvas::new(self).width(Length::Fill).height(Length::Fill))
@@ -126,8 +304,7 @@ impl App {
             // information popup, when there is no selection
             .push_maybe(self.selection.is_none().then(|| {
                 super::WelcomeMessage {
-                    image_width: self.image.width(),
-                    image_height: self.image.height(),
+                    monitor: self.image.active_monitor().rect(),
                 }
                 .view()
             }))
@@ -159,6 +336,41 @@ impl App {
                     .view()
                 },
             ))
+            // keys which continue the sequence that was started
+            .push_maybe((!self.pending_keys.is_empty()).then(|| {
+                super::WhichKey {
+                    pending_keys: &self.pending_keys,
+                    monitor: self.image.active_monitor().rect(),
+                }
+                .view()
+            }))
+            // keys of a sequence which was started, and the count typed before it
+            .push_maybe(
+                (!self.pending_keys.is_empty() || self.motion_count.is_some()).then(|| {
+                    super::PendingKeys {
+                        keys: &self.pending_keys,
+                        count: self.motion_count,
+                        monitor: self.image.active_monitor().rect(),
+                    }
+                    .view()
+                }),
+            )
+            // progress of the upload
+            .push_maybe(self.uploading.as_ref().map(|uploading| {
+                super::UploadProgress {
+                    uploading,
+                    monitor: self.image.active_monitor().rect(),
+                }
+                .view()
+            }))
+            // link to the uploaded image
+            .push_maybe(self.uploaded_image.as_ref().map(|uploaded_image| {
+                super::UploadResult {
+                    uploaded_image,
+                    monitor: self.image.active_monitor().rect(),
+                }
+                .view()
+            }))
             .into()
     }
 
@@ -312,7 +524,29 @@ impl App {
 
                 self.selection = Some(new_selection);
             }
-            Message::KeyBind { action, count } => match action {
+            Message::InputPressed {
+                input,
+                modifiers,
+                cursor,
+            } => {
+                self.cursor_position = cursor;
+                return self.press_key(input, modifiers);
+            }
+            Message::KeyTimeout { key_press } => {
+                // another key was pressed since, which has its own timeout
+                if key_press != self.key_presses {
+                    return Task::none();
+                }
+
+                if let Some(action) = CONFIG.keys.timeout(&mut self.pending_keys) {
+                    return self.trigger_keybind(action, None);
+                }
+            }
+            Message::KeyBind {
+                action,
+                count,
+                input,
+            } => match action {
                 KeyAction::ClearSelection => {
                     self.selection = None;
                 }
@@ -325,6 +559,15 @@ impl App {
                     ));
                 }
                 KeyAction::CopyToClipboard => {
+                    // with `--output`, the screenshot is written there instead
+                    if CLI.output.is_some() {
+                        return self.update(Message::KeyBind {
+                            action: KeyAction::SaveScreenshot,
+                            count,
+                            input,
+                        });
+                    }
+
                     let Some(selection) = self.selection.map(Selection::norm) else {
                         self.errors.push("There is no selection to copy");
                         return Task::none();
@@ -336,18 +579,41 @@ impl App {
                         self.image.bytes(),
                     );
 
-                    let image_data = arboard::ImageData {
-                        width: cropped_image.width() as usize,
-                        height: cropped_image.height() as usize,
-                        bytes: std::borrow::Cow::Borrowed(cropped_image.as_bytes()),
+                    // with `copy-file`, the screenshot is saved first, so that the file
+                    // can be offered alongside the image
+                    let saved_path = if CONFIG.copy_file {
+                        match crate::auto_save::save_to_directory(
+                            &cropped_image,
+                            &CONFIG.save_directory,
+                            &CONFIG.filename_template,
+                            &self.image.monitor_at(selection.rect.center()).name,
+                            CONFIG.format,
+                            &CONFIG.encode_options(),
+                        ) {
+                            Ok(path) => Some(std::path::absolute(&path).unwrap_or(path)),
+                            Err(err) => {
+                                let err = format!("Could not save the screenshot: {err}");
+                                Report::failure(Status::SaveFailed, Some(Action::Copy), &err)
+                                    .record();
+                                self.errors.push(err);
+                                return Task::none();
+                            }
+                        }
+                    } else {
+                        None
                     };
-
-                    #[cfg_attr(
-                        target_os = "macos",
-                        expect(unused_variables, reason = "it is used on other platforms")
-                    )]
-                    match crate::clipboard::set_image(image_data) {
-                        Ok(img_path) => {
+                    let uri = saved_path.as_deref().map(crate::clipboard::file_uri);
+                    let path_text = saved_path
+                        .as_deref()
+                        .filter(|_| CONFIG.copy_path_as_text)
+                        .map(|path| path.to_string_lossy());
+
+                    match crate::clipboard::set(&crate::clipboard::Content {
+                        image: Some(&cropped_image),
+                        uri: uri.as_deref(),
+                        text: path_text.as_deref(),
+                    }) {
+                        Ok(()) => {
                             // send desktop notification if possible, this is
                             // just a decoration though so it's ok if we fail to do this
                             let mut notify = notify_rust::Notification::new();
@@ -358,16 +624,35 @@ impl App {
                                 h = cropped_image.height()
                             ));
 
-                            // images are not supported on macos
-                            #[cfg(not(target_os = "macos"))]
-                            notify.image_path(&img_path.to_string_lossy());
+                            // the image is passed from memory, so that no copy of it
+                            // is left behind on the disk. Only supported on these platforms
+                            #[cfg(all(unix, not(target_os = "macos")))]
+                            if let Some(image) = i32::try_from(cropped_image.width())
+                                .ok()
+                                .zip(i32::try_from(cropped_image.height()).ok())
+                                .and_then(|(width, height)| {
+                                    notify_rust::Image::from_rgba(
+                                        width,
+                                        height,
+                                        cropped_image.to_rgba8().into_raw(),
+                                    )
+                                    .ok()
+                                })
+                            {
+                                notify.image_data(image);
+                            }
 
                             let _ = notify.show();
 
+                            crate::capture_history::record(cropped_image.clone());
+                            Report::success(Action::Copy, selection.rect).record();
+
                             return Self::exit();
                         }
                         Err(err) => {
-                            self.errors.push(format!("Could not copy the image: {err}"));
+                            let err = format!("Could not copy the image: {err}");
+                            Report::failure(Status::CopyFailed, Some(Action::Copy), &err).record();
+                            self.errors.push(err);
                         }
                     }
                 }
@@ -385,10 +670,100 @@ impl App {
                         self.image.bytes(),
                     );
 
+                    let Some(output) = &CLI.output else {
+                        return Self::save_with_file_dialog(cropped_image, selection.rect);
+                    };
+
+                    // the image is written after the app closes, at which point we can't show
+                    // errors to the user anymore. So check that we are able to write it beforehand
+                    if let Err(err) = crate::headless::output_format(output)
+                        .and_then(|format| format.validate(&CONFIG.encode_options()))
+                    {
+                        self.errors
+                            .push(format!("Cannot save the screenshot: {err}"));
+                        return Task::none();
+                    }
+
+                    crate::capture_history::record(cropped_image.clone());
                     let _ = SAVED_IMAGE.set(cropped_image);
 
+                    // `main` updates the report once the image is actually written
+                    Report::success(Action::Output, selection.rect).record();
+
                     return Self::exit();
                 }
+                KeyAction::SaveToDirectory => {
+                    let Some(selection) = self.selection.map(Selection::norm) else {
+                        self.errors.push("There is no selection to save");
+                        return Task::none();
+                    };
+
+                    let cropped_image = selection.process_image(
+                        self.image.width(),
+                        self.image.height(),
+                        self.image.bytes(),
+                    );
+
+                    match crate::auto_save::save_to_directory(
+                        &cropped_image,
+                        &CONFIG.save_directory,
+                        &CONFIG.filename_template,
+                        &self.image.monitor_at(selection.rect.center()).name,
+                        CONFIG.format,
+                        &CONFIG.encode_options(),
+                    ) {
+                        Ok(path) => {
+                            // the summary already contains the path, and standard output
+                            // might be reserved for the image itself
+                            if CLI.print_result.is_none() && !CLI.writes_to_stdout() {
+                                println!("{}", path.display());
+                            }
+
+                            // just a decoration, so it's ok if we fail to show the notification
+                            let mut notify = notify_rust::Notification::new();
+                            notify.summary(&format!("Saved screenshot to {}", path.display()));
+                            #[cfg(not(target_os = "macos"))]
+                            notify.image_path(&path.to_string_lossy());
+                            let _ = notify.show();
+
+                            crate::capture_history::record(cropped_image.clone());
+                            Report::success(Action::SaveToDirectory, selection.rect)
+                                .with_path(path)
+                                .record();
+
+                            return Self::exit();
+                        }
+                        Err(err) => {
+                            let err = format!("Could not save the screenshot: {err}");
+                            Report::failure(
+                                Status::SaveFailed,
+                                Some(Action::SaveToDirectory),
+                                &err,
+                            )
+                            .record();
+                            self.errors.push(err);
+                        }
+                    }
+                }
+                KeyAction::CopyAsDataUri => {
+                    return self.copy_snippet(|_, image, _| {
+                        crate::clipboard::snippet::data_uri(image)
+                            .map_err(|err| format!("Could not encode the image: {err}"))
+                    });
+                }
+                KeyAction::CopyAsMarkdown => {
+                    return self.copy_snippet(|app, image, selection| {
+                        app.snippet_source(image, selection)
+                            .map(|src| crate::clipboard::snippet::markdown(&src))
+                    });
+                }
+                KeyAction::CopyAsHtml => {
+                    return self.copy_snippet(|app, image, selection| {
+                        app.snippet_source(image, selection).map(|src| {
+                            crate::clipboard::snippet::html(&src, (image.width(), image.height()))
+                        })
+                    });
+                }
                 KeyAction::Exit => return Self::exit(),
                 KeyAction::SetWidth => {
                     let Some(selection) = self.selection.as_mut() else {
@@ -512,11 +887,28 @@ impl App {
                 KeyAction::PickBottomRightCorner => {
                     self.picking_corner = Some(PickCorner::BottomRight);
                 }
+                KeyAction::SnapToCursor => {
+                    if let Some((cursor_pos, sel)) =
+                        self.cursor_position.zip(self.selection.as_mut())
+                    {
+                        // only a held mouse button is released, which stops the resize
+                        let follow =
+                            matches!(input, Some(Input::Mouse(mouse)) if mouse.is_button());
+                        sel.snap_to_cursor(cursor_pos, follow);
+                    }
+                }
+                // unbound keys are removed when building the `KeyMap`, so they never trigger anything
+                KeyAction::Unbind => {}
             },
             Message::ExtendNewSelection(new_mouse_position) => {
                 self.update_selection(new_mouse_position);
             }
             Message::Upload => {
+                // one upload at a time
+                if self.uploading.is_some() {
+                    return Task::none();
+                }
+
                 let Some(selection) = self.selection.as_ref().map(|sel| Selection::norm(*sel))
                 else {
                     self.errors
@@ -530,41 +922,148 @@ impl App {
                     self.image.bytes(),
                 );
 
-                let tempfile = match tempfile::TempDir::new() {
-                    Ok(tempdir) => tempdir.into_path().join("ferrishot-screenshot.png"),
+                let image = match crate::image_upload::EncodedImage::png(&cropped_image) {
+                    Ok(image) => image,
                     Err(err) => {
-                        self.errors.push(err.to_string());
+                        self.errors
+                            .push(format!("Could not encode the image: {err}"));
                         return Task::none();
                     }
                 };
 
-                if let Err(err) = cropped_image.save_with_format(&tempfile, image::ImageFormat::Png)
-                {
-                    self.errors.push(err.to_string());
-                }
+                let size = (cropped_image.width(), cropped_image.height());
 
-                return Task::future(async move {
-                    {
-                        let file = tempfile;
-                        let response = CONFIG
-                            .default_image_upload_provider
-                            .upload_image(&file)
-                            .await;
-
-                        match response {
-                            Ok(url) => Message::ImageUploaded { url },
-                            Err(err) => Message::Error(err.to_string()),
-                        }
+                // added before the upload, so that it can be uploaded again from the
+                // history if it fails
+                let local_path = crate::capture_history::record_with_path(cropped_image.clone());
+
+                // progress is sent through the channel while the upload runs. The result is sent
+                // through it too, so that it always comes after the progress
+                let (sender, receiver) = iced::futures::channel::mpsc::unbounded();
+                let on_event: crate::image_upload::OnEvent = {
+                    let sender = sender.clone();
+                    std::sync::Arc::new(move |event| {
+                        let _ = sender.unbounded_send(Message::UploadEvent(event));
+                    })
+                };
+
+                let upload = async move {
+                    let result = async {
+                        crate::image_upload::upload_with_fallbacks(
+                            &CONFIG.upload_providers_in_order()?,
+                            crate::http::client()?,
+                            &image,
+                            crate::image_upload::RetryPolicy::from_config(&CONFIG),
+                            &on_event,
+                        )
+                        .await
                     }
-                });
+                    .await;
+
+                    let message = match result {
+                        Ok((provider, uploaded)) => {
+                            crate::upload_history::record_upload(
+                                crate::upload_history::Upload::new(
+                                    &provider.name,
+                                    &uploaded,
+                                    size,
+                                    // `None` if the capture history is disabled
+                                    local_path.await.ok(),
+                                ),
+                            );
+                            Message::ImageUploaded { url: uploaded.link }
+                        }
+                        Err(err) => Message::UploadFailed(err.to_string()),
+                    };
+                    let _ = sender.unbounded_send(message);
+                };
+
+                let (task, handle) = Task::run(
+                    iced::futures::stream::select(
+                        receiver,
+                        iced::futures::stream::once(upload)
+                            .filter_map(|()| std::future::ready(None)),
+                    ),
+                    std::convert::identity,
+                )
+                .abortable();
+                self.uploading = Some(super::Uploading::new(handle, cropped_image, selection.rect));
+
+                return task;
+            }
+            Message::UploadEvent(event) => {
+                if let Some(uploading) = &mut self.uploading {
+                    uploading.update(event);
+                }
+            }
+            Message::CancelUpload => {
+                if let Some(uploading) = self.uploading.take() {
+                    uploading.handle.abort();
+                }
             }
             Message::ImageUploaded { url } => {
-                self.uploaded_url = Some(url);
+                let Some(uploading) = self.uploading.take() else {
+                    return Task::none();
+                };
+                Report::success(Action::Upload, uploading.rect)
+                    .with_url(url.clone())
+                    .record();
+                self.uploaded_image = Some(super::UploadedImage::new(
+                    url,
+                    uploading.image,
+                    uploading.rect,
+                ));
+            }
+            Message::UploadFailed(err) => {
+                self.uploading = None;
+                Report::failure(Status::UploadFailed, Some(Action::Upload), &err).record();
+                self.errors.push(err);
+            }
+            Message::ExitImageUploadMenu => self.uploaded_image = None,
+            Message::CopyUploadedUrl => {
+                let Some(uploaded_image) = &self.uploaded_image else {
+                    return Task::none();
+                };
+
+                match crate::clipboard::set(&crate::clipboard::Content {
+                    // the image which was uploaded, as the selection may have moved since
+                    image: Some(&uploaded_image.image).filter(|_| CONFIG.copy_image_with_link),
+                    uri: Some(&uploaded_image.url),
+                    text: Some(&uploaded_image.url),
+                }) {
+                    Ok(()) => {
+                        // just a decoration, so it's ok if we fail to show the notification
+                        let _ = notify_rust::Notification::new()
+                            .summary(&format!("Copied link to clipboard: {}", uploaded_image.url))
+                            .show();
+                    }
+                    Err(err) => {
+                        self.errors.push(format!("Could not copy the link: {err}"));
+                    }
+                }
             }
-            Message::ExitImageUploadMenu => self.uploaded_url = None,
             Message::Error(err) => {
                 self.errors.push(err);
             }
+            Message::ScreenshotSaved {
+                saved,
+                rect,
+                window,
+                mode,
+            } => match saved {
+                Ok(Some(path)) => {
+                    Report::success(Action::Save, rect).with_path(path).record();
+                    return Self::exit();
+                }
+                // the file dialog was closed, so go back to the selection
+                Ok(None) => return iced::window::set_mode(window, mode),
+                Err(err) => {
+                    let err = format!("Failed to save the screenshot: {err}");
+                    Report::failure(Status::SaveFailed, Some(Action::Save), &err).record();
+                    self.errors.push(err);
+                    return iced::window::set_mode(window, mode);
+                }
+            },
             Message::Resize {
                 current_cursor_pos,
                 initial_cursor_pos,
@@ -601,26 +1100,6 @@ impl App {
                     }
                 }
             }
-            Message::ResizeToCursor {
-                cursor_pos,
-                selection,
-                sel_is_some,
-            } => {
-                let (corner_point, corners) = selection.corners().nearest_corner(cursor_pos);
-                let sel = self.selection.unlock(sel_is_some);
-
-                sel.rect = corners.resize_rect(
-                    selection.rect,
-                    cursor_pos.y - corner_point.y,
-                    cursor_pos.x - corner_point.x,
-                );
-
-                sel.status = SelectionStatus::Resize {
-                    initial_rect: sel.rect,
-                    initial_cursor_pos: cursor_pos,
-                    resize_side: SideOrCorner::Corner(corners),
-                };
-            }
         }
 
         Task::none()
@@ -632,10 +1111,8 @@ impl App {
 pub struct AppKeysState {
     /// Left mouse click is currently being held down
     pub is_left_down: bool,
-    /// How many times to execute the next motion
-    pub motion_count: Option<u32>,
-    /// The last key that was pressed
-    pub last_key_pressed: Option<iced::keyboard::Key>,
+    /// Modifiers which are currently held down, except for Shift. Mouse events don't include them
+    pub modifiers: iced::keyboard::Modifiers,
 }
 
 impl canvas::Program<Message> for App {
@@ -672,31 +1149,14 @@ impl canvas::Program<Message> for App {
         cursor: iced::advanced::mouse::Cursor,
     ) -> Option<Action<Message>> {
         use iced::Event::{Keyboard, Mouse};
-        use iced::keyboard::Event::KeyPressed;
+        use iced::keyboard::Event::{KeyPressed, ModifiersChanged};
         use iced::keyboard::Key::Named;
         use iced::keyboard::Modifiers;
-        use iced::keyboard::key::Named::Shift;
+        use iced::keyboard::key::Named::{Alt, AltGraph, Control, Hyper, Meta, Shift, Super};
         use iced::mouse::Button::Left;
         use iced::mouse::Event::ButtonPressed;
         use iced::mouse::Event::ButtonReleased;
-
-        // handle the number pressed
-        //
-        // pressing numbers will have an effect, e.g. `200j` will
-        // move the selection down by 200px
-        if let Keyboard(KeyPressed {
-            key: iced::keyboard::Key::Character(ch),
-            ..
-        }) = event
-        {
-            if let Ok(number_pressed) = ch.parse::<u32>() {
-                if let Some(motion_count) = state.motion_count.as_mut() {
-                    *motion_count = *motion_count * 10 + number_pressed;
-                } else {
-                    state.motion_count = Some(number_pressed);
-                }
-            }
-        }
+        use iced::mouse::Event::WheelScrolled;
 
         // handle keybindings
         if let Keyboard(KeyPressed {
@@ -705,6 +1165,18 @@ impl canvas::Program<Message> for App {
             ..
         }) = event
         {
+            // Modifiers on their own do not take part in keybindings, they are held
+            // while pressing other keys. Otherwise, pressing `g` then `ctrl + x` would
+            // interrupt the `g` sequence with the `ctrl` key
+            //
+            // The "Shift" is already included in the `modified_key`, e.g. 'G'
+            if matches!(
+                modified_key,
+                Named(Shift | Control | Alt | AltGraph | Super | Meta | Hyper)
+            ) {
+                return None;
+            }
+
             let mut modifiers = *modifiers;
 
             // Shift key does not matter. For example:
@@ -714,45 +1186,30 @@ impl canvas::Program<Message> for App {
             // We also forbid the user from specifying `shift` as a modifier in their `config.kdl`
             modifiers.remove(Modifiers::SHIFT);
 
-            if let Some(action) = state
-                .last_key_pressed
-                .as_ref()
-                .and_then(|last_key_pressed| {
-                    CONFIG.keys.get(
-                        last_key_pressed.clone(),
-                        Some(modified_key.clone()),
-                        modifiers,
-                    )
-                })
-                .or_else(|| CONFIG.keys.get(modified_key.clone(), None, modifiers))
-            {
-                // the last key pressed needs to be reset for it to be
-                // correct in future invocations
-                //
-                // For example if I press `gg`, and it activates some keybinding
-                // I would have to press `gg` *again* to active it.
-                //
-                // If we did not reset, then `ggg` would trigger the `gg` keybindings
-                // twice
-                state.last_key_pressed = None;
-
-                let count = state.motion_count.unwrap_or(1);
-                state.motion_count = None;
-
-                return Some(Action::publish(Message::KeyBind {
-                    action: action.clone(),
-                    count,
-                }));
-            }
+            return Some(Action::publish(Message::InputPressed {
+                input: Input::Key(modified_key.clone()),
+                modifiers,
+                cursor: cursor.position(),
+            }));
+        }
 
-            // the "Shift" is already included in the modifiers
-            //
-            // Otherwise, when pressing 'G' for instance it would first set
-            // - `last_key_pressed = Shift` then
-            // - `last_key_pressed = 'G'`
-            if *modified_key != Named(Shift) {
-                state.last_key_pressed = Some(modified_key.clone());
-            }
+        if let Keyboard(ModifiersChanged(modifiers)) = event {
+            state.modifiers = *modifiers;
+            state.modifiers.remove(Modifiers::SHIFT);
+        }
+
+        // mouse buttons other than the left one, and the scroll wheel, can be bound to actions
+        let mouse_input = match event {
+            Mouse(ButtonPressed(button)) => MouseInput::from_button(*button),
+            Mouse(WheelScrolled { delta }) => MouseInput::from_scroll(*delta),
+            _ => None,
+        };
+        if let Some(mouse_input) = mouse_input {
+            return Some(Action::publish(Message::InputPressed {
+                input: Input::Mouse(mouse_input),
+                modifiers: state.modifiers,
+                cursor: cursor.position(),
+            }));
         }
 
         // Create the selection when it does not exist yet
@@ -770,6 +1227,7 @@ impl canvas::Program<Message> for App {
                     Message::KeyBind {
                         action: KeyAction::CopyToClipboard,
                         count: 1,
+                        input: None,
                     }
                 } else {
                     // stop the creating of the initial selection
@@ -80,6 +80,10 @@ impl Errors {
             .width(ERROR_WIDTH)
             .spacing(30);
 
-        row![Space::with_width(image_width - ERROR_WIDTH), errors].into()
+        row![
+            Space::with_width(image_width.saturating_sub(ERROR_WIDTH)),
+            errors
+        ]
+        .into()
     }
 }
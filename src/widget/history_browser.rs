@@ -0,0 +1,252 @@
+//! Browse the capture history, opened with `ferrishot --history`
+//!
+//! Past captures can be copied, saved, uploaded again or deleted
+
+use iced::{
+    Element, Font, Length, Task,
+    widget::{Column, Row, Space, button, column, image, row, scrollable, text},
+};
+
+use crate::{
+    CONFIG,
+    capture_history::{self, Capture, THUMBNAIL_SIZE},
+};
+
+/// Amount of captures in each row
+const COLUMNS: usize = 4;
+/// Size of the font of the buttons and labels
+const FONT_SIZE: f32 = 13.0;
+
+/// Something the user did in the history browser
+#[derive(Debug, Clone)]
+pub enum HistoryMessage {
+    /// Copy the capture to the clipboard
+    Copy(Capture),
+    /// Save the capture to a file chosen in the file dialog
+    Save(Capture),
+    /// The capture was saved to the path, the file dialog was closed, or saving failed
+    Saved(Result<Option<std::path::PathBuf>, String>),
+    /// Upload the capture to the internet
+    Upload(Capture),
+    /// The capture was uploaded, or failed to upload
+    Uploaded(Result<String, String>),
+    /// Remove the capture from the history
+    Delete(Capture),
+}
+
+/// Lists the captures in the history, newest first
+#[derive(Debug, Default)]
+pub struct HistoryBrowser {
+    /// Captures in the history, newest first
+    captures: Vec<Capture>,
+    /// What happened after the last action
+    status: Option<String>,
+    /// Whether a capture is being uploaded
+    uploading: bool,
+}
+
+impl HistoryBrowser {
+    /// Read the captures in the history
+    pub fn new() -> Self {
+        let mut browser = Self::default();
+        browser.refresh();
+        browser
+    }
+
+    /// Read the captures again, after some of them were removed
+    fn refresh(&mut self) {
+        match capture_history::history_dir().and_then(|dir| capture_history::list(&dir)) {
+            Ok(captures) => self.captures = captures,
+            Err(err) => self.status = Some(err.to_string()),
+        }
+    }
+
+    /// Respond to the message
+    pub fn update(&mut self, message: HistoryMessage) -> Task<HistoryMessage> {
+        match message {
+            HistoryMessage::Copy(capture) => {
+                let copied = capture
+                    .load()
+                    .map_err(|err| err.to_string())
+                    .and_then(|image| {
+                        crate::clipboard::set(&crate::clipboard::Content {
+                            image: Some(&image),
+                            ..Default::default()
+                        })
+                        .map_err(|err| err.to_string())
+                    });
+
+                self.status = Some(match copied {
+                    Ok(()) => String::from("Copied the capture to the clipboard"),
+                    Err(err) => format!("Could not copy the capture: {err}"),
+                });
+            }
+            HistoryMessage::Save(capture) => {
+                let image = match capture.load() {
+                    Ok(image) => image,
+                    Err(err) => {
+                        self.status = Some(format!("Could not save the capture: {err}"));
+                        return Task::none();
+                    }
+                };
+
+                return Task::perform(
+                    async move {
+                        crate::image_format::save_with_file_dialog(&image)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    HistoryMessage::Saved,
+                );
+            }
+            HistoryMessage::Saved(saved) => match saved {
+                Ok(Some(path)) => {
+                    self.status = Some(format!("Saved the capture to {}", path.display()));
+                }
+                // the file dialog was closed
+                Ok(None) => {}
+                Err(err) => self.status = Some(format!("Could not save the capture: {err}")),
+            },
+            HistoryMessage::Upload(capture) => {
+                if self.uploading {
+                    return Task::none();
+                }
+
+                let image = match capture.load() {
+                    Ok(image) => image,
+                    Err(err) => {
+                        self.status = Some(format!("Could not upload the capture: {err}"));
+                        return Task::none();
+                    }
+                };
+                let size = (image.width(), image.height());
+                let encoded = match crate::image_upload::EncodedImage::png(&image) {
+                    Ok(encoded) => encoded,
+                    Err(err) => {
+                        self.status = Some(format!("Could not encode the capture: {err}"));
+                        return Task::none();
+                    }
+                };
+
+                self.uploading = true;
+                self.status = Some(String::from("Uploading..."));
+
+                let upload = async move {
+                    let on_event: crate::image_upload::OnEvent = std::sync::Arc::new(|_| {});
+                    let (provider, uploaded) = crate::image_upload::upload_with_fallbacks(
+                        &CONFIG.upload_providers_in_order()?,
+                        crate::http::client()?,
+                        &encoded,
+                        crate::image_upload::RetryPolicy::from_config(&CONFIG),
+                        &on_event,
+                    )
+                    .await?;
+
+                    crate::upload_history::record_upload(crate::upload_history::Upload::new(
+                        &provider.name,
+                        &uploaded,
+                        size,
+                        Some(capture.image),
+                    ));
+
+                    Ok::<_, crate::image_upload::UploadError>(uploaded.link)
+                };
+
+                return Task::perform(upload, |result| {
+                    HistoryMessage::Uploaded(result.map_err(|err| err.to_string()))
+                });
+            }
+            HistoryMessage::Uploaded(result) => {
+                self.uploading = false;
+                self.status = Some(match result {
+                    Ok(url) => match crate::clipboard::set_text(&url) {
+                        Ok(()) => format!("Uploaded to {url}, the link was copied"),
+                        Err(err) => {
+                            format!("Uploaded to {url}, but could not copy the link: {err}")
+                        }
+                    },
+                    Err(err) => format!("Could not upload the capture: {err}"),
+                });
+            }
+            HistoryMessage::Delete(capture) => {
+                if let Err(err) = capture.delete() {
+                    self.status = Some(format!("Could not delete the capture: {err}"));
+                }
+                self.refresh();
+            }
+        }
+
+        Task::none()
+    }
+
+    /// Render the history
+    pub fn view(&self) -> Element<HistoryMessage> {
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::default()
+        };
+
+        let captures: Element<HistoryMessage> = if self.captures.is_empty() {
+            text(if CONFIG.history_size == 0 {
+                "The history is disabled. Set `history-size` in the config to keep recent captures"
+            } else {
+                "There are no captures yet. Copy, save or upload a screenshot to add it here"
+            })
+            .size(FONT_SIZE)
+            .into()
+        } else {
+            scrollable(
+                Column::with_children(self.captures.chunks(COLUMNS).map(|captures| {
+                    Row::with_children(captures.iter().map(|capture| self.card(capture)))
+                        .spacing(10.0)
+                        .into()
+                }))
+                .spacing(10.0),
+            )
+            .height(Length::Fill)
+            .into()
+        };
+
+        column![
+            text("Capture history").size(FONT_SIZE * 1.5).font(bold),
+            text(self.status.as_deref().unwrap_or_default()).size(FONT_SIZE),
+            captures,
+        ]
+        .spacing(10.0)
+        .padding(10.0)
+        .into()
+    }
+
+    /// A capture, with buttons to act on it
+    fn card<'a>(&self, capture: &'a Capture) -> Element<'a, HistoryMessage> {
+        let action = |label: &'static str, message: HistoryMessage| {
+            button(text(label).size(FONT_SIZE)).on_press(message)
+        };
+
+        let taken_at = capture.taken_at().map_or_else(
+            || capture.id.clone(),
+            |taken_at| taken_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        );
+
+        column![
+            image(image::Handle::from_path(&capture.thumbnail))
+                .width(THUMBNAIL_SIZE as f32)
+                .height(THUMBNAIL_SIZE as f32),
+            text(taken_at).size(FONT_SIZE),
+            row![
+                action("Copy", HistoryMessage::Copy(capture.clone())),
+                action("Save", HistoryMessage::Save(capture.clone())),
+                // one upload at a time
+                button(text("Upload").size(FONT_SIZE)).on_press_maybe(
+                    (!self.uploading).then(|| HistoryMessage::Upload(capture.clone()))
+                ),
+                Space::with_width(Length::Fill),
+                action("Delete", HistoryMessage::Delete(capture.clone())),
+            ]
+            .spacing(5.0),
+        ]
+        .spacing(5.0)
+        .width(THUMBNAIL_SIZE as f32)
+        .into()
+    }
+}
@@ -5,19 +5,29 @@ use iced::Element;
 mod app;
 mod background_image;
 mod errors;
+mod history_browser;
 mod letters;
+mod pending_keys;
 pub mod selection;
 mod selection_icons;
 mod size_indicator;
+mod upload_progress;
+mod upload_result;
 mod welcome_message;
+mod which_key;
 
 pub use app::{App, SAVED_IMAGE};
 pub use background_image::BackgroundImage;
 pub use errors::Errors;
+pub use history_browser::{HistoryBrowser, HistoryMessage};
 pub use letters::{Letters, PickCorner};
+pub use pending_keys::PendingKeys;
 pub use selection_icons::SelectionIcons;
 pub use size_indicator::SizeIndicator;
+pub use upload_progress::{UploadProgress, Uploading};
+pub use upload_result::{UploadResult, UploadedImage};
 pub use welcome_message::WelcomeMessage;
+pub use which_key::WhichKey;
 
 /// An extension trait to show a red border around an element and all children
 #[easy_ext::ext(Explainer)]
@@ -0,0 +1,76 @@
+//! Shows the keys of a key sequence which was started, while waiting for the rest of it
+
+use iced::{
+    Background, Color, Element, Font, Length, Rectangle,
+    alignment::Horizontal,
+    widget::{Space, column, container, row, text, text::Shaping},
+};
+
+use crate::{
+    CONFIG,
+    config::{KeyPress, key_press_name},
+    message::Message,
+};
+
+/// Size of the font of the pending keys
+const FONT_SIZE: f32 = 16.0;
+/// Approximate height of the box, used to place it at the bottom of the monitor
+const HEIGHT: f32 = 40.0;
+/// Space between the box and the edges of the monitor
+const MARGIN: f32 = 20.0;
+
+/// Box in the bottom right corner of the monitor with the pending keys, e.g. `12g`
+#[derive(Debug, Clone, Copy)]
+pub struct PendingKeys<'a> {
+    /// Keys pressed so far
+    pub keys: &'a [KeyPress],
+    /// Number typed before the keys
+    pub count: Option<u32>,
+    /// Area of the monitor on which to show the box
+    pub monitor: Rectangle,
+}
+
+impl<'a> PendingKeys<'a> {
+    /// Render the pending keys
+    pub fn view(self) -> Element<'a, Message> {
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::default()
+        };
+
+        let keys = self
+            .count
+            .map(|count| count.to_string())
+            .into_iter()
+            .chain(self.keys.iter().map(key_press_name))
+            .collect::<String>();
+
+        let panel = container(
+            text(keys)
+                .size(FONT_SIZE)
+                .font(bold)
+                .shaping(Shaping::Advanced),
+        )
+        .padding([6.0, 12.0])
+        .style(|_| iced::widget::container::Style {
+            text_color: Some(CONFIG.theme.info_box_fg),
+            background: Some(Background::Color(CONFIG.theme.info_box_bg)),
+            border: iced::Border::default()
+                .color(Color::WHITE)
+                .rounded(6.0)
+                .width(1.5),
+            shadow: iced::Shadow::default(),
+        });
+
+        column![
+            Space::with_height(self.monitor.y + self.monitor.height - HEIGHT - MARGIN),
+            row![
+                Space::with_width(self.monitor.x),
+                container(panel)
+                    .width(Length::Fixed(self.monitor.width - MARGIN))
+                    .align_x(Horizontal::Right),
+            ],
+        ]
+        .into()
+    }
+}
@@ -243,6 +243,30 @@ impl Selection {
         })
     }
 
+    /// Move the corner nearest to the cursor onto the cursor.
+    ///
+    /// With `follow`, the corner keeps following the cursor until the mouse button is released
+    pub fn snap_to_cursor(&mut self, cursor_pos: Point, follow: bool) {
+        let selection = self.norm();
+        let (corner_point, corner) = selection.corners().nearest_corner(cursor_pos);
+
+        self.rect = corner.resize_rect(
+            selection.rect,
+            cursor_pos.y - corner_point.y,
+            cursor_pos.x - corner_point.x,
+        );
+
+        self.status = if follow {
+            SelectionStatus::Resize {
+                initial_rect: self.rect,
+                initial_cursor_pos: cursor_pos,
+                resize_side: SideOrCorner::Corner(corner),
+            }
+        } else {
+            SelectionStatus::Idle
+        };
+    }
+
     delegate! {
         to self.rect {
             /// The height and width of the selection
@@ -297,8 +321,6 @@ impl Selection {
 pub struct SelectionKeysState {
     /// Left mouse click is currently being held down
     pub is_left_down: bool,
-    /// Left mouse click is currently being held down
-    pub is_right_down: bool,
     /// Shift key is currently being held down
     pub is_shift_down: bool,
 }
@@ -370,7 +392,7 @@ impl canvas::Program<Message> for Selection {
         use iced::keyboard::Event::KeyReleased;
         use iced::keyboard::Key::Named;
         use iced::keyboard::key::Named::Shift;
-        use iced::mouse::Button::{Left, Right};
+        use iced::mouse::Button::Left;
         use iced::mouse::Event::ButtonPressed;
         use iced::mouse::Event::ButtonReleased;
         use iced::mouse::Event::CursorMoved;
@@ -479,18 +501,8 @@ impl canvas::Program<Message> for Selection {
                     },
                 }
             }
-            Mouse(ButtonPressed(Right)) => {
-                state.is_right_down = true;
-
-                Message::ResizeToCursor {
-                    cursor_pos: cursor.position()?,
-                    selection: self.norm(),
-                    sel_is_some: SelectionIsSome { _private: () },
-                }
-            }
-            Mouse(ButtonReleased(Right)) => {
-                state.is_right_down = false;
-
+            // stop resizing after `snap-to-cursor` was triggered by another mouse button
+            Mouse(ButtonReleased(button)) if *button != Left && self.is_resize() => {
                 Message::EnterIdle
             }
             Mouse(CursorMoved { position }) if self.is_create() => {
@@ -502,3 +514,37 @@ impl canvas::Program<Message> for Selection {
         Some(Action::publish(message))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snap_to_cursor() {
+        let selection = Selection::new(Point::new(0.0, 0.0)).with_size(|_| Size::new(100.0, 100.0));
+        let cursor = Point::new(90.0, 120.0);
+
+        let mut snapped = selection;
+        snapped.snap_to_cursor(cursor, false);
+        assert_eq!(
+            snapped.rect,
+            Rectangle::new(Point::new(0.0, 0.0), Size::new(90.0, 120.0)),
+            "the nearest corner moves to the cursor"
+        );
+        assert!(
+            snapped.is_idle(),
+            "keys and the scroll wheel snap once, as they are never released"
+        );
+
+        let mut following = selection;
+        following.snap_to_cursor(cursor, true);
+        assert_eq!(
+            following.rect, snapped.rect,
+            "following the cursor snaps the same way"
+        );
+        assert!(
+            following.is_resize(),
+            "the corner follows the cursor while the mouse button is held"
+        );
+    }
+}
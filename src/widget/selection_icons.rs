@@ -158,6 +158,7 @@ impl SelectionIcons {
                     .on_press(Message::KeyBind {
                         action: KeyAction::SelectFullScreen,
                         count: 1,
+                        input: None,
                     })
                     .into(),
                 "Select entire monitor (F11)",
@@ -167,6 +168,7 @@ impl SelectionIcons {
                     .on_press(Message::KeyBind {
                         action: KeyAction::CopyToClipboard,
                         count: 1,
+                        input: None,
                     })
                     .into(),
                 "Copy to Clipboard (Enter)",
@@ -176,6 +178,7 @@ impl SelectionIcons {
                     .on_press(Message::KeyBind {
                         action: KeyAction::SaveScreenshot,
                         count: 1,
+                        input: None,
                     })
                     .into(),
                 "Save Screenshot (Ctrl + S)",
@@ -185,6 +188,7 @@ impl SelectionIcons {
                     .on_press(Message::KeyBind {
                         action: KeyAction::Exit,
                         count: 1,
+                        input: None,
                     })
                     .into(),
                 "Exit (Esc)",
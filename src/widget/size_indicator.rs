@@ -66,9 +66,9 @@ impl SizeIndicator {
         const ESTIMATED_INDICATOR_HEIGHT: u32 = 26;
 
         let x_offset = (self.selection_rect.bottom_right().x + SPACING)
-            .min((self.image_width - ESTIMATED_INDICATOR_WIDTH) as f32);
+            .min(self.image_width.saturating_sub(ESTIMATED_INDICATOR_WIDTH) as f32);
         let y_offset = (self.selection_rect.bottom_right().y + SPACING)
-            .min((self.image_height - ESTIMATED_INDICATOR_HEIGHT) as f32);
+            .min(self.image_height.saturating_sub(ESTIMATED_INDICATOR_HEIGHT) as f32);
 
         let horizontal_space = Space::with_width(x_offset);
         let vertical_space = Space::with_height(y_offset);
@@ -0,0 +1,138 @@
+//! Shows how far along the upload is, with a button to cancel it
+
+use iced::{
+    Background, Color, Element, Font, Length, Rectangle,
+    widget::{Space, button, column, progress_bar, row, text},
+};
+
+use crate::{CONFIG, image_upload::UploadEvent, message::Message};
+
+/// Width of the upload progress box
+const WIDTH: f32 = 380.0;
+/// Approximate height of the upload progress box, used to center it
+const HEIGHT: f32 = 110.0;
+/// Size of the font in the upload progress box
+const FONT_SIZE: f32 = 13.0;
+
+/// An upload which has not finished yet
+#[derive(Debug)]
+pub struct Uploading {
+    /// Aborts the upload
+    pub handle: iced::task::Handle,
+    /// Name of the provider we are currently uploading to
+    pub provider: String,
+    /// Which attempt it is for this provider, starting at 1
+    pub attempt: u32,
+    /// Bytes sent so far, and the size of the whole image
+    pub progress: Option<(u64, u64)>,
+    /// The image being uploaded, which is copied along with its link
+    pub image: image::DynamicImage,
+    /// The selection which is being uploaded
+    pub rect: iced::Rectangle,
+}
+
+impl Uploading {
+    /// Start tracking the upload of the `image` of the selected `rect`,
+    /// which can be aborted with the `handle`
+    pub fn new(
+        handle: iced::task::Handle,
+        image: image::DynamicImage,
+        rect: iced::Rectangle,
+    ) -> Self {
+        Self {
+            handle,
+            provider: String::new(),
+            attempt: 1,
+            progress: None,
+            image,
+            rect,
+        }
+    }
+
+    /// Keep track of what happened
+    pub fn update(&mut self, event: UploadEvent) {
+        match event {
+            UploadEvent::Attempt { provider, attempt } => {
+                self.provider = provider;
+                self.attempt = attempt;
+                self.progress = None;
+            }
+            UploadEvent::Progress { sent, total } => self.progress = Some((sent, total)),
+        }
+    }
+
+    /// How much of the image was sent, from 0 to 1
+    fn fraction(&self) -> f32 {
+        match self.progress {
+            Some((sent, total)) if total > 0 => sent as f32 / total as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Panel with the progress of the upload and a button to cancel it
+#[derive(Debug, Clone, Copy)]
+pub struct UploadProgress<'a> {
+    /// The upload
+    pub uploading: &'a Uploading,
+    /// Area of the monitor on which to center the panel
+    pub monitor: Rectangle,
+}
+
+impl<'a> UploadProgress<'a> {
+    /// Render the upload progress
+    pub fn view(self) -> Element<'a, Message> {
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::default()
+        };
+
+        let status = match (self.uploading.attempt, self.uploading.progress) {
+            // the whole image was sent, we're waiting for the link
+            (_, Some((sent, total))) if sent == total => String::from("Waiting for the link..."),
+            (1, _) => format!("{:.0}%", self.uploading.fraction() * 100.0),
+            (attempt, _) => format!(
+                "{:.0}% (attempt {attempt})",
+                self.uploading.fraction() * 100.0
+            ),
+        };
+
+        let content = column![
+            text(format!("Uploading to {}", self.uploading.provider))
+                .size(FONT_SIZE)
+                .font(bold),
+            progress_bar(0.0..=1.0, self.uploading.fraction()).height(8.0),
+            row![
+                text(status).size(FONT_SIZE),
+                Space::with_width(Length::Fill),
+                button(text("Cancel").size(FONT_SIZE))
+                    .on_press(Message::CancelUpload)
+                    .style(|_, _| button::Style {
+                        text_color: CONFIG.theme.icon_fg,
+                        ..button::Style::default().with_background(CONFIG.theme.icon_bg)
+                    }),
+            ]
+            .align_y(iced::Alignment::Center),
+        ]
+        .spacing(10.0)
+        .width(WIDTH)
+        .padding(10.0);
+
+        let panel = iced::widget::container(content).style(|_| iced::widget::container::Style {
+            text_color: Some(CONFIG.theme.info_box_fg),
+            background: Some(Background::Color(CONFIG.theme.info_box_bg)),
+            border: iced::Border::default()
+                .color(Color::WHITE)
+                .rounded(6.0)
+                .width(1.5),
+            shadow: iced::Shadow::default(),
+        });
+
+        let vertical_space =
+            Space::with_height(self.monitor.height.mul_add(0.5, self.monitor.y) - HEIGHT / 2.0);
+        let horizontal_space =
+            Space::with_width(self.monitor.width.mul_add(0.5, self.monitor.x) - WIDTH / 2.0);
+
+        column![vertical_space, row![horizontal_space, panel]].into()
+    }
+}
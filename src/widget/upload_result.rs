@@ -0,0 +1,230 @@
+//! Shows the link to the uploaded image, with a QR code so it can be opened on a phone
+
+use iced::{
+    Background, Color, Element, Font, Length, Rectangle, Renderer, Theme,
+    widget::{Canvas, Space, button, canvas, column, row, text, text::Shaping},
+};
+
+use crate::{CONFIG, message::Message};
+
+/// Width of the upload result box
+const WIDTH: f32 = 380.0;
+/// Approximate height of the upload result box, used to center it
+const HEIGHT: f32 = 340.0;
+/// Width and height of the QR code
+const QR_CODE_SIZE: f32 = 220.0;
+/// Amount of empty modules around the QR code. Scanners need it to find the code
+const QUIET_ZONE: usize = 4;
+/// Size of the font in the upload result box
+const FONT_SIZE: f32 = 13.0;
+
+/// A QR code, generated locally without sending the link anywhere
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    /// Amount of modules in each row and column
+    width: usize,
+    /// For each module, row by row: whether it is dark
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode the text as a QR code
+    pub fn new(text: &str) -> Result<Self, qrcode::types::QrError> {
+        let code = qrcode::QrCode::new(text.as_bytes())?;
+
+        Ok(Self {
+            width: code.width(),
+            modules: code
+                .to_colors()
+                .into_iter()
+                .map(|color| color == qrcode::Color::Dark)
+                .collect(),
+        })
+    }
+
+    /// Whether the module at the given column and row is dark
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+}
+
+impl canvas::Program<Message> for QrCode {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: iced::advanced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        // QR codes are always dark on light, no matter the theme. Otherwise some
+        // scanners can't read them
+        frame.fill_rectangle(iced::Point::ORIGIN, bounds.size(), Color::WHITE);
+
+        let module_size = bounds.width.min(bounds.height) / (self.width + QUIET_ZONE * 2) as f32;
+
+        for y in 0..self.width {
+            for x in 0..self.width {
+                if self.is_dark(x, y) {
+                    frame.fill_rectangle(
+                        iced::Point::new(
+                            (x + QUIET_ZONE) as f32 * module_size,
+                            (y + QUIET_ZONE) as f32 * module_size,
+                        ),
+                        iced::Size::new(module_size, module_size),
+                        Color::BLACK,
+                    );
+                }
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// An image that was uploaded to the internet
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedImage {
+    /// Link to the image
+    pub url: String,
+    /// QR code of the link. `None` if the link is too long to fit in a QR code
+    pub qr_code: Option<QrCode>,
+    /// The image which was uploaded. The selection may have changed since
+    pub image: image::DynamicImage,
+    /// The selection which was uploaded
+    pub rect: iced::Rectangle,
+}
+
+impl UploadedImage {
+    /// Create the QR code for the link to the `image` of the selected `rect`
+    pub fn new(url: String, image: image::DynamicImage, rect: iced::Rectangle) -> Self {
+        Self {
+            qr_code: QrCode::new(&url)
+                .inspect_err(|err| log::warn!("Could not create a QR code for {url}: {err}"))
+                .ok(),
+            url,
+            image,
+            rect,
+        }
+    }
+}
+
+/// Panel with the link to the uploaded image, a button to copy it and its QR code
+#[derive(Debug, Clone, Copy)]
+pub struct UploadResult<'a> {
+    /// The uploaded image
+    pub uploaded_image: &'a UploadedImage,
+    /// Area of the monitor on which to center the panel
+    pub monitor: Rectangle,
+}
+
+impl<'a> UploadResult<'a> {
+    /// Render the upload result
+    pub fn view(self) -> Element<'a, Message> {
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::default()
+        };
+
+        let styled_button = |label: &'static str, message: Message| {
+            button(text(label).size(FONT_SIZE))
+                .on_press(message)
+                .style(|_, _| button::Style {
+                    text_color: CONFIG.theme.icon_fg,
+                    ..button::Style::default().with_background(CONFIG.theme.icon_bg)
+                })
+        };
+
+        let content = column![
+            text("Screenshot uploaded").size(FONT_SIZE).font(bold),
+            text(&self.uploaded_image.url)
+                .size(FONT_SIZE)
+                .shaping(Shaping::Advanced),
+            row![
+                styled_button("Copy link", Message::CopyUploadedUrl),
+                Space::with_width(Length::Fill),
+                styled_button("Close", Message::ExitImageUploadMenu),
+            ],
+        ]
+        .push_maybe(self.uploaded_image.qr_code.clone().map(|qr_code| {
+            row![
+                Space::with_width(Length::Fill),
+                Canvas::new(qr_code)
+                    .width(QR_CODE_SIZE)
+                    .height(QR_CODE_SIZE),
+                Space::with_width(Length::Fill),
+            ]
+        }))
+        .spacing(10.0)
+        .width(WIDTH)
+        .padding(10.0);
+
+        let panel = iced::widget::container(content).style(|_| iced::widget::container::Style {
+            text_color: Some(CONFIG.theme.info_box_fg),
+            background: Some(Background::Color(CONFIG.theme.info_box_bg)),
+            border: iced::Border::default()
+                .color(Color::WHITE)
+                .rounded(6.0)
+                .width(1.5),
+            shadow: iced::Shadow::default(),
+        });
+
+        let vertical_space =
+            Space::with_height(self.monitor.height.mul_add(0.5, self.monitor.y) - HEIGHT / 2.0);
+        let horizontal_space =
+            Space::with_width(self.monitor.width.mul_add(0.5, self.monitor.x) - WIDTH / 2.0);
+
+        column![vertical_space, row![horizontal_space, panel]].into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn qr_code_has_finder_patterns() {
+        let qr_code = QrCode::new("https://0x0.st/abcd.png").expect("short links fit");
+
+        assert_eq!(
+            qr_code.modules.len(),
+            qr_code.width * qr_code.width,
+            "there is a module for each row and column"
+        );
+
+        // each corner except the bottom-right has a 7x7 finder pattern, whose
+        // outer ring is dark and separated from the rest by a light ring
+        for (x, y) in [(0, 0), (qr_code.width - 7, 0), (0, qr_code.width - 7)] {
+            assert!(
+                qr_code.is_dark(x, y),
+                "corner of the finder pattern at ({x}, {y})"
+            );
+            assert!(
+                qr_code.is_dark(x + 6, y + 6),
+                "opposite corner of the finder pattern at ({x}, {y})"
+            );
+            assert!(
+                !qr_code.is_dark(x + 1, y + 1),
+                "light ring of the finder pattern at ({x}, {y})"
+            );
+        }
+    }
+
+    #[test]
+    fn link_too_long_for_qr_code() {
+        let uploaded_image = UploadedImage::new(
+            "a".repeat(8000),
+            image::DynamicImage::new_rgba8(1, 1),
+            iced::Rectangle::default(),
+        );
+
+        assert!(
+            uploaded_image.qr_code.is_none(),
+            "the link is still shown without a QR code"
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! The welcome message contains tips on how to use ferrishot
 
 use iced::{
-    Background, Color, Element, Font, Length,
+    Background, Color, Element, Font, Length, Rectangle,
     alignment::Vertical,
     widget::{Space, column, row, text, text::Shaping},
 };
@@ -16,19 +16,20 @@ const HEIGHT: u32 = 160;
 const FONT_SIZE: f32 = 13.0;
 
 /// Renders the welcome message that the user sees when they first launch the program
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct WelcomeMessage {
-    /// Width of the container of the welcome message
-    pub image_width: u32,
-    /// Height of the container of the welcome message
-    pub image_height: u32,
+    /// Area of the monitor on which to center the welcome message
+    pub monitor: Rectangle,
 }
 
 impl WelcomeMessage {
     /// Render the welcome message
     pub fn view(self) -> Element<'static, Message> {
-        let vertical_space = Space::with_height(self.image_height / 2 - HEIGHT / 2);
-        let horizontal_space = Space::with_width(self.image_width / 2 - WIDTH / 2);
+        let vertical_space = Space::with_height(
+            self.monitor.height.mul_add(0.5, self.monitor.y) - HEIGHT as f32 / 2.0,
+        );
+        let horizontal_space =
+            Space::with_width(self.monitor.width.mul_add(0.5, self.monitor.x) - WIDTH as f32 / 2.0);
 
         let bold = Font {
             weight: iced::font::Weight::Bold,
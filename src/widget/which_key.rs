@@ -0,0 +1,106 @@
+//! Lists the keys which continue a key sequence that was started, e.g. `gg` and `gh` after `g`
+
+use iced::{
+    Background, Color, Element, Font, Length, Rectangle,
+    alignment::{Horizontal, Vertical},
+    widget::{Column, Row, Space, column, container, row, text, text::Shaping},
+};
+
+use crate::{
+    CONFIG,
+    config::{Continuation, KeyPress, key_press_name},
+    message::Message,
+};
+
+/// Size of the font of the actions
+const FONT_SIZE: f32 = 13.0;
+/// Width of the column with the keys
+const KEY_WIDTH: f32 = 60.0;
+/// Width of each entry
+const ENTRY_WIDTH: f32 = 340.0;
+/// Amount of entries in each column of the popup
+const ROWS: usize = 8;
+/// Space below the popup, which is left for the pending keys
+const BOTTOM_SPACE: f32 = 80.0;
+
+/// Popup at the bottom of the monitor with the keys that can be pressed next,
+/// and what each of them does
+#[derive(Debug, Clone, Copy)]
+pub struct WhichKey<'a> {
+    /// Keys pressed so far
+    pub pending_keys: &'a [KeyPress],
+    /// Area of the monitor on which to show the popup
+    pub monitor: Rectangle,
+}
+
+impl<'a> WhichKey<'a> {
+    /// Render the popup
+    pub fn view(self) -> Element<'a, Message> {
+        let continuations = CONFIG.keys.continuations(self.pending_keys);
+
+        let popup = container(
+            Row::with_children(continuations.chunks(ROWS).map(|entries| {
+                Column::with_children(entries.iter().map(|entry| Self::entry(*entry)))
+                    .spacing(6.0)
+                    .into()
+            }))
+            .spacing(20.0),
+        )
+        .padding(12.0)
+        .style(|_| iced::widget::container::Style {
+            text_color: Some(CONFIG.theme.which_key_fg),
+            background: Some(Background::Color(CONFIG.theme.which_key_bg)),
+            border: iced::Border::default()
+                .color(Color::WHITE)
+                .rounded(6.0)
+                .width(1.5),
+            shadow: iced::Shadow::default(),
+        });
+
+        column![
+            Space::with_height(self.monitor.y),
+            row![
+                Space::with_width(self.monitor.x),
+                container(popup)
+                    .width(Length::Fixed(self.monitor.width))
+                    .height(Length::Fixed(self.monitor.height - BOTTOM_SPACE))
+                    .align_x(Horizontal::Center)
+                    .align_y(Vertical::Bottom),
+            ],
+        ]
+        .into()
+    }
+
+    /// A key, with the action it triggers and the documentation of the action
+    fn entry(continuation: Continuation<'_>) -> Element<'a, Message> {
+        let bold = Font {
+            weight: iced::font::Weight::Bold,
+            ..Font::default()
+        };
+
+        let description: Element<'a, Message> = match continuation.action {
+            Some(action) => column![
+                text(action.to_string()).size(FONT_SIZE).font(bold),
+                text(action.summary()).size(FONT_SIZE * 0.9),
+            ]
+            .into(),
+            // only the start of longer sequences
+            None => text(format!("+{} keybindings", continuation.longer))
+                .size(FONT_SIZE)
+                .font(bold)
+                .into(),
+        };
+
+        row![
+            text(key_press_name(continuation.key))
+                .size(FONT_SIZE)
+                .font(bold)
+                .color(CONFIG.theme.which_key_key)
+                .shaping(Shaping::Advanced)
+                .width(KEY_WIDTH),
+            description,
+        ]
+        .width(ENTRY_WIDTH)
+        .into()
+    }
+}